@@ -1,6 +1,11 @@
-use clap::{Parser as ClapParser, Subcommand as ClapSubcommand};
+use std::{ffi::OsString, path::Path};
 
-use crate::utils::logging;
+use clap::{
+    error::ErrorKind, CommandFactory, FromArgMatches, Parser as ClapParser,
+    Subcommand as ClapSubcommand,
+};
+
+use crate::{server, utils::logging};
 
 /// Discv5-cli Args
 #[allow(missing_docs)]
@@ -23,10 +28,105 @@ pub struct Cli {
 #[derive(ClapSubcommand, Clone, Debug)]
 #[allow(missing_docs)]
 pub enum Subcommand {
+    #[clap(
+        name = "conformance",
+        about = "Runs protocol conformance checks against a node"
+    )]
+    Conformance(crate::conformance::Conformance),
+    #[clap(name = "distance", about = "Calculates the distance between node ids")]
+    Distance(crate::distance::Distance),
+    #[clap(
+        name = "findnode",
+        about = "Sends a FINDNODE request to a node and prints the returned ENRs"
+    )]
+    FindNode(crate::findnode::FindNode),
+    #[clap(name = "keys", about = "Generates, inspects and converts node keys")]
+    Keys(crate::keys::Keys),
     #[clap(name = "packet", about = "Performs packet operations")]
     Packet(crate::packet::Packet),
+    #[clap(name = "ping", about = "Pings a node and reports round-trip times")]
+    Ping(crate::ping::Ping),
+    #[clap(
+        name = "portal",
+        about = "Sends Portal wire protocol requests to a node"
+    )]
+    Portal(crate::portal::Portal),
     #[clap(name = "request-enr", about = "Requests an ENR from a node")]
     RequestEnr(crate::request_enr::RequestEnr),
     #[clap(name = "server", about = "Runs a discv5 test server")]
-    Server(crate::server::Server),
+    Server(Box<crate::server::Server>),
+    #[clap(
+        name = "talk",
+        about = "Sends a TALKREQ to a node and prints the response"
+    )]
+    Talk(crate::talk::Talk),
+}
+
+impl Cli {
+    /// Parses the command line arguments, reading the defaults of the server options from the
+    /// `--config` file if one is given. Exits on invalid arguments.
+    pub fn parse_with_config() -> Self {
+        Self::try_parse_with_config(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parses the arguments, reading the defaults of the server options from the `--config` file
+    /// if one is given. The file selects the service if the command line does not.
+    pub fn try_parse_with_config<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let (config, has_service) = match Self::try_parse_from(&args) {
+            Ok(cli) => match &cli.subcommand {
+                Some(Subcommand::Server(server)) => match &server.config {
+                    Some(config) => (config.clone(), true),
+                    None => return Ok(cli),
+                },
+                _ => return Ok(cli),
+            },
+            // The service may be selected by the configuration file
+            Err(e) if e.kind() == ErrorKind::MissingSubcommand => {
+                let matches = Self::command()
+                    .mut_subcommand("server", |server| server.subcommand_required(false))
+                    .try_get_matches_from(&args);
+                match matches.ok().as_ref().and_then(|matches| {
+                    matches
+                        .subcommand_matches("server")?
+                        .get_one::<String>("config")
+                }) {
+                    Some(config) => (config.clone(), false),
+                    None => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        };
+
+        let values = server::config::load(Path::new(&config)).map_err(|e| {
+            Self::command().error(
+                ErrorKind::Io,
+                format!("Failed to read the configuration file {config}: {e}"),
+            )
+        })?;
+        let invalid = |e: eyre::Report| {
+            Self::command().error(
+                ErrorKind::InvalidValue,
+                format!("Invalid configuration file {config}: {e}"),
+            )
+        };
+        if !has_service {
+            let service =
+                server::config::service_args(&Self::command(), &values).map_err(invalid)?;
+            if service.is_empty() {
+                return Err(Self::command().error(
+                    ErrorKind::MissingSubcommand,
+                    format!("No service is selected on the command line or in {config}"),
+                ));
+            }
+            args.extend(service.into_iter().map(OsString::from));
+        }
+        let mut command = server::config::apply(Self::command(), values).map_err(invalid)?;
+        let matches = command.try_get_matches_from_mut(args)?;
+        Self::from_arg_matches(&matches).map_err(|e| e.format(&mut command))
+    }
 }
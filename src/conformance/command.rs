@@ -0,0 +1,26 @@
+use clap::Args;
+
+use crate::utils::local_node::LocalNode;
+
+/// Conformance
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Conformance {
+    /// The node to test.
+    #[clap(
+        short = 'm',
+        long = "target",
+        allow_hyphen_values = true,
+        help = "The node to test, as an ENR, multiaddr, enode URL or <public-key>@<ip>:<port>"
+    )]
+    pub target: String,
+    /// How long to wait for the node to request our updated ENR.
+    #[clap(
+        long = "enr-update-timeout",
+        help = "The number of seconds to wait for the node to request our ENR after its sequence number was increased.",
+        default_value = "5"
+    )]
+    pub enr_update_timeout: u64,
+    /// The local node the checks are run from.
+    #[clap(flatten)]
+    pub local_node: LocalNode,
+}
@@ -0,0 +1,502 @@
+//! Handles running protocol conformance checks against a node
+
+use std::{collections::HashSet, fmt, time::Duration};
+
+use discv5::{
+    enr::{self, NodeId},
+    handler::NodeContact,
+    rpc::{RequestBody, ResponseBody},
+    Key, RequestError,
+};
+
+use crate::{
+    request_enr::target::Target,
+    utils::{local_node::LocalNode, raw_node::RawNode},
+};
+
+/// The [clap] cli command arguments for the conformance service.
+pub mod command;
+pub use command::*;
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// A protocol name no node is expected to support.
+const UNKNOWN_PROTOCOL: &[u8] = b"discv5-cli-conformance";
+
+/// The distances requested to provoke a NODES response split across packets.
+const MULTI_PACKET_DISTANCES: [u64; 8] = [256, 255, 254, 253, 252, 251, 250, 249];
+
+/// The outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The node behaved as expected.
+    Pass,
+    /// The node did not behave as expected.
+    Fail,
+    /// The check could not be run against the node.
+    Skip,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "PASS"),
+            Outcome::Fail => write!(f, "FAIL"),
+            Outcome::Skip => write!(f, "SKIP"),
+        }
+    }
+}
+
+/// The result of a single check.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// The name of the check.
+    pub name: &'static str,
+    /// The outcome of the check.
+    pub outcome: Outcome,
+    /// A description of what was observed.
+    pub detail: String,
+}
+
+/// The results of all checks.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// The results in the order the checks were run.
+    pub results: Vec<CheckResult>,
+}
+
+impl Report {
+    /// Whether no check failed.
+    pub fn passed(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.outcome != Outcome::Fail)
+    }
+
+    /// The result of the named check.
+    pub fn get(&self, name: &str) -> Option<&CheckResult> {
+        self.results.iter().find(|result| result.name == name)
+    }
+
+    fn record(&mut self, name: &'static str, outcome: Outcome, detail: impl Into<String>) {
+        let detail = detail.into();
+        log::debug!("{} {}: {}", outcome, name, detail);
+        self.results.push(CheckResult {
+            name,
+            outcome,
+            detail,
+        });
+    }
+}
+
+/// The node under test.
+struct Suite {
+    node: RawNode,
+    target: Target,
+    node_id: NodeId,
+    /// The latest ENR of the node, if known.
+    enr: Option<Enr>,
+}
+
+impl Suite {
+    /// The contact requests are sent to, preferring the latest known ENR.
+    fn contact(&self) -> Result<NodeContact, String> {
+        match &self.enr {
+            Some(enr) => Target::Enr(enr.clone()).contact(self.node.ip_mode()),
+            None => self.target.contact(self.node.ip_mode()),
+        }
+    }
+
+    async fn request(&mut self, body: RequestBody) -> Result<Vec<ResponseBody>, String> {
+        let contact = self.contact()?;
+        self.node
+            .request(contact, body)
+            .await
+            .map_err(|e| describe_error(&e))
+    }
+
+    /// Requests the distances and returns the number of packets, the announced total and the ENRs.
+    async fn find_node(&mut self, distances: Vec<u64>) -> Result<(u64, u64, Vec<Enr>), String> {
+        let mut packets = 0;
+        let mut total = 0;
+        let mut enrs = Vec::new();
+        for response in self.request(RequestBody::FindNode { distances }).await? {
+            match response {
+                ResponseBody::Nodes {
+                    total: announced,
+                    nodes,
+                } => {
+                    packets += 1;
+                    total = announced;
+                    enrs.extend(nodes);
+                }
+                body => return Err(format!("unexpected response {body}")),
+            }
+        }
+        Ok((packets, total, enrs))
+    }
+
+    async fn ping(&mut self) -> Result<(u64, String), String> {
+        let enr_seq = self.node.enr().seq();
+        match self.request(RequestBody::Ping { enr_seq }).await?.pop() {
+            Some(ResponseBody::Pong { enr_seq, ip, port }) => {
+                Ok((enr_seq, format!("{}:{}", ip, port)))
+            }
+            Some(body) => Err(format!("unexpected response {body}")),
+            None => Err("no response".into()),
+        }
+    }
+
+    /// A fresh node key guarantees the node has never seen our ENR, so it must obtain it from
+    /// the handshake. Any response proves a session was established.
+    async fn check_handshake(&mut self, report: &mut Report) -> bool {
+        match self.ping().await {
+            Ok(_) => {
+                report.record(
+                    "handshake",
+                    Outcome::Pass,
+                    "session established with a node key unknown to the node",
+                );
+                true
+            }
+            Err(e) => {
+                report.record("handshake", Outcome::Fail, e);
+                false
+            }
+        }
+    }
+
+    async fn check_ping(&mut self, report: &mut Report) {
+        match self.ping().await {
+            Ok((enr_seq, observed)) => {
+                let known_seq = self.enr.as_ref().map(|enr| enr.seq()).unwrap_or_default();
+                if enr_seq < known_seq {
+                    report.record(
+                        "ping",
+                        Outcome::Fail,
+                        format!(
+                            "PONG enr_seq {enr_seq} is lower than the known ENR seq {known_seq}"
+                        ),
+                    );
+                } else {
+                    report.record(
+                        "ping",
+                        Outcome::Pass,
+                        format!("PONG enr_seq {enr_seq}, observed us at {observed}"),
+                    );
+                }
+            }
+            Err(e) => report.record("ping", Outcome::Fail, e),
+        }
+    }
+
+    async fn check_findnode_zero(&mut self, report: &mut Report) {
+        let name = "findnode-0";
+        match self.find_node(vec![0]).await {
+            Ok((_, _, enrs)) => match enrs.as_slice() {
+                [enr] if enr.node_id() != self.node_id => report.record(
+                    name,
+                    Outcome::Fail,
+                    format!("returned the ENR of {} instead of its own", enr.node_id()),
+                ),
+                [enr] => {
+                    let known_seq = self.enr.as_ref().map(|enr| enr.seq()).unwrap_or_default();
+                    if enr.seq() < known_seq {
+                        report.record(
+                            name,
+                            Outcome::Fail,
+                            format!(
+                                "returned seq {} older than the known seq {known_seq}",
+                                enr.seq()
+                            ),
+                        );
+                    } else {
+                        report.record(
+                            name,
+                            Outcome::Pass,
+                            format!("returned its own ENR with seq {}", enr.seq()),
+                        );
+                        self.enr = Some(enr.clone());
+                    }
+                }
+                enrs => report.record(
+                    name,
+                    Outcome::Fail,
+                    format!("returned {} ENRs instead of its own", enrs.len()),
+                ),
+            },
+            Err(e) => report.record(name, Outcome::Fail, e),
+        }
+    }
+
+    async fn check_findnode_256(&mut self, report: &mut Report) {
+        let name = "findnode-256";
+        match self.find_node(vec![256]).await {
+            Ok((packets, total, enrs)) => {
+                let key = Key::from(self.node_id);
+                let wrong = enrs
+                    .iter()
+                    .filter(|enr| key.log2_distance(&Key::from(enr.node_id())) != Some(256))
+                    .count();
+                if wrong > 0 {
+                    report.record(
+                        name,
+                        Outcome::Fail,
+                        format!("{wrong} of {} ENRs are not at distance 256", enrs.len()),
+                    );
+                } else if packets < total {
+                    report.record(
+                        name,
+                        Outcome::Fail,
+                        format!("received {packets} of {total} NODES packets"),
+                    );
+                } else {
+                    report.record(
+                        name,
+                        Outcome::Pass,
+                        format!("{} ENRs at distance 256", enrs.len()),
+                    );
+                }
+            }
+            Err(e) => report.record(name, Outcome::Fail, e),
+        }
+    }
+
+    /// Distances above 256 must not return ENRs. Ignoring the request is allowed, as long as the
+    /// node keeps responding.
+    async fn check_invalid_distances(&mut self, report: &mut Report) {
+        let name = "invalid-distances";
+        match self.find_node(vec![257, 1000, u64::MAX]).await {
+            Ok((_, _, enrs)) if enrs.is_empty() => {
+                report.record(name, Outcome::Pass, "returned no ENRs")
+            }
+            Ok((_, _, enrs)) => {
+                report.record(name, Outcome::Fail, format!("returned {} ENRs", enrs.len()))
+            }
+            Err(_) => match self.ping().await {
+                Ok(_) => report.record(
+                    name,
+                    Outcome::Pass,
+                    "the request was ignored and the node still responds",
+                ),
+                Err(e) => report.record(
+                    name,
+                    Outcome::Fail,
+                    format!("the node stopped responding: {e}"),
+                ),
+            },
+        }
+    }
+
+    async fn check_multi_packet(&mut self, report: &mut Report) {
+        let name = "multi-packet-nodes";
+        match self.find_node(MULTI_PACKET_DISTANCES.to_vec()).await {
+            Ok((_, total, enrs)) if total <= 1 => report.record(
+                name,
+                Outcome::Skip,
+                format!(
+                    "the node only knows {} ENRs, which fit into a single packet",
+                    enrs.len()
+                ),
+            ),
+            Ok((packets, total, enrs)) => {
+                let unique = enrs.iter().map(|enr| enr.node_id()).collect::<HashSet<_>>();
+                if packets < total {
+                    report.record(
+                        name,
+                        Outcome::Fail,
+                        format!("received {packets} of {total} NODES packets"),
+                    );
+                } else if unique.len() != enrs.len() {
+                    report.record(
+                        name,
+                        Outcome::Fail,
+                        format!(
+                            "{} duplicate ENRs across packets",
+                            enrs.len() - unique.len()
+                        ),
+                    );
+                } else {
+                    report.record(
+                        name,
+                        Outcome::Pass,
+                        format!("{} ENRs split across {packets} NODES packets", enrs.len()),
+                    );
+                }
+            }
+            Err(e) => report.record(name, Outcome::Fail, e),
+        }
+    }
+
+    async fn check_unknown_talk(&mut self, report: &mut Report) {
+        let name = "talk-unknown-protocol";
+        let body = RequestBody::Talk {
+            protocol: UNKNOWN_PROTOCOL.to_vec(),
+            request: b"ping".to_vec(),
+        };
+        match self
+            .request(body)
+            .await
+            .map(|mut responses| responses.pop())
+        {
+            Ok(Some(ResponseBody::Talk { response })) if response.is_empty() => {
+                report.record(name, Outcome::Pass, "empty TALKRESP")
+            }
+            Ok(Some(ResponseBody::Talk { response })) => report.record(
+                name,
+                Outcome::Fail,
+                format!("TALKRESP with {} bytes", response.len()),
+            ),
+            Ok(Some(body)) => {
+                report.record(name, Outcome::Fail, format!("unexpected response {body}"))
+            }
+            Ok(None) => report.record(name, Outcome::Fail, "no response"),
+            Err(e) => report.record(name, Outcome::Fail, e),
+        }
+    }
+
+    /// A PING with a higher ENR sequence number should make the node request our new ENR.
+    async fn check_enr_update(&mut self, report: &mut Report, timeout: Duration) {
+        let name = "enr-seq-update";
+        let seq = match self.node.update_enr("conformance", b"updated") {
+            Ok(seq) => seq,
+            Err(e) => {
+                return report.record(
+                    name,
+                    Outcome::Skip,
+                    format!("could not update our ENR: {e}"),
+                )
+            }
+        };
+        if let Err(e) = self.ping().await {
+            return report.record(name, Outcome::Fail, e);
+        }
+        let node_id = self.node_id;
+        let requested = |address: &discv5::handler::NodeAddress, request: &discv5::rpc::Request| {
+            address.node_id == node_id
+                && matches!(&request.body, RequestBody::FindNode { distances } if distances.contains(&0))
+        };
+        let already_requested = self
+            .node
+            .received()
+            .iter()
+            .any(|(address, request)| requested(address, request));
+        if already_requested
+            || self
+                .node
+                .wait_for_request(timeout, requested)
+                .await
+                .is_some()
+        {
+            report.record(
+                name,
+                Outcome::Pass,
+                format!("the node requested our ENR after seq was raised to {seq}"),
+            );
+        } else {
+            report.record(
+                name,
+                Outcome::Fail,
+                format!(
+                    "the node did not request our ENR within {}s of a PING with seq {seq}. Nodes only do so for peers in their routing table, so our ENR must be reachable from the node",
+                    timeout.as_secs()
+                ),
+            );
+        }
+    }
+}
+
+/// Describes a request failure in terms of the node's behaviour.
+fn describe_error(error: &RequestError) -> String {
+    match error {
+        RequestError::Timeout => "no response".into(),
+        error => error.to_string(),
+    }
+}
+
+/// Runs all checks against the target.
+pub async fn run_checks(
+    local_node: &LocalNode,
+    target: &Target,
+    enr_update_timeout: Duration,
+) -> eyre::Result<Report> {
+    let node_id = target
+        .node_id()
+        .ok_or_else(|| eyre::eyre!("The node id of the target is unknown"))?;
+    let node = RawNode::start(local_node, target.is_ipv6()).await?;
+    let enr = match target {
+        Target::Enr(enr) => Some(enr.clone()),
+        Target::Multiaddr(_) => None,
+    };
+    let mut suite = Suite {
+        node,
+        target: target.clone(),
+        node_id,
+        enr,
+    };
+
+    let mut report = Report::default();
+    if !suite.check_handshake(&mut report).await {
+        // None of the remaining checks can succeed without a session
+        return Ok(report);
+    }
+    suite.check_ping(&mut report).await;
+    suite.check_findnode_zero(&mut report).await;
+    suite.check_findnode_256(&mut report).await;
+    suite.check_invalid_distances(&mut report).await;
+    suite.check_multi_packet(&mut report).await;
+    suite.check_unknown_talk(&mut report).await;
+    suite
+        .check_enr_update(&mut report, enr_update_timeout)
+        .await;
+    Ok(report)
+}
+
+/// Runs the conformance command. Returns whether all checks passed.
+pub async fn run(conformance: &Conformance) -> bool {
+    let target = match conformance.target.parse::<Target>() {
+        Ok(target) => target,
+        Err(e) => {
+            log::error!("Invalid target provided. Error: {}", e);
+            return false;
+        }
+    };
+
+    log::info!("Running conformance checks against {}", target);
+    let report = match run_checks(
+        &conformance.local_node,
+        &target,
+        Duration::from_secs(conformance.enr_update_timeout),
+    )
+    .await
+    {
+        Ok(report) => report,
+        Err(e) => {
+            log::error!("Failed to run the conformance checks. Error: {}", e);
+            return false;
+        }
+    };
+
+    for result in &report.results {
+        println!(
+            "{:<4} {:<22} {}",
+            result.outcome.to_string(),
+            result.name,
+            result.detail
+        );
+    }
+    let count = |outcome| {
+        report
+            .results
+            .iter()
+            .filter(|result| result.outcome == outcome)
+            .count()
+    };
+    log::info!(
+        "{} passed, {} failed, {} skipped",
+        count(Outcome::Pass),
+        count(Outcome::Fail),
+        count(Outcome::Skip)
+    );
+    report.passed()
+}
@@ -0,0 +1,25 @@
+use clap::Args;
+
+/// Distance Command
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Distance {
+    /// The node to measure the distance from.
+    #[clap(
+        help = "The node to measure from, as a node id, ENR, public key, peer id or `key:`-prefixed private key."
+    )]
+    pub node_a: String,
+    /// The node to measure the distance to.
+    #[clap(
+        required_unless_present = "rank_file",
+        conflicts_with = "rank_file",
+        help = "The node to measure to, in any of the formats accepted for the first node."
+    )]
+    pub node_b: Option<String>,
+    /// A file of ENRs to rank by their distance to the first node.
+    #[clap(
+        short = 'r',
+        long = "rank-file",
+        help = "A file with one ENR (or other node identifier) per line to rank by distance to the first node. Use - to read from stdin."
+    )]
+    pub rank_file: Option<String>,
+}
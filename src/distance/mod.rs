@@ -0,0 +1,99 @@
+//! Handles the node id distance calculations
+
+use std::io::{BufRead, BufReader};
+
+use discv5::{enr::NodeId, Key};
+
+use crate::utils::node_id;
+
+/// The [clap] cli command arguments for the distance service.
+pub mod command;
+pub use command::*;
+
+/// The distance between two node ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeDistance {
+    /// The XOR of the two node ids.
+    pub xor: [u8; 32],
+    /// The log2 distance, i.e. the bucket one node falls in within the other's routing table.
+    /// `None` if the node ids are equal.
+    pub log2: Option<u64>,
+    /// The number of leading bits the node ids share.
+    pub shared_prefix: u64,
+}
+
+impl NodeDistance {
+    /// Calculates the distance between two node ids.
+    pub fn between(a: &NodeId, b: &NodeId) -> Self {
+        let mut xor = [0u8; 32];
+        for (i, byte) in xor.iter_mut().enumerate() {
+            *byte = a.raw()[i] ^ b.raw()[i];
+        }
+        let key_a: Key<NodeId> = (*a).into();
+        let log2 = key_a.log2_distance(&(*b).into());
+        NodeDistance {
+            xor,
+            log2,
+            shared_prefix: 256 - log2.unwrap_or(0),
+        }
+    }
+}
+
+/// Prints the distance between two nodes, or ranks a list of nodes by distance to the first, to
+/// stdout.
+pub fn run(distance: &Distance) -> eyre::Result<()> {
+    let node_a = node_id::parse(&distance.node_a)?;
+
+    if let Some(node_b) = &distance.node_b {
+        let node_b = node_id::parse(node_b)?;
+        let result = NodeDistance::between(&node_a, &node_b);
+        println!("Node A: 0x{}", hex::encode(node_a.raw()));
+        println!("Node B: 0x{}", hex::encode(node_b.raw()));
+        println!("XOR distance: 0x{}", hex::encode(result.xor));
+        println!("Log2 distance (bucket): {}", result.log2.unwrap_or(0));
+        println!("Shared prefix length: {}", result.shared_prefix);
+        return Ok(());
+    }
+
+    if let Some(rank_file) = &distance.rank_file {
+        let reader: Box<dyn BufRead> = if rank_file == "-" {
+            Box::new(BufReader::new(std::io::stdin()))
+        } else {
+            Box::new(BufReader::new(std::fs::File::open(rank_file)?))
+        };
+
+        let mut ranked = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let entry = line.trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            match node_id::parse(entry) {
+                Ok(node_id) => ranked.push((
+                    NodeDistance::between(&node_a, &node_id),
+                    node_id,
+                    entry.to_string(),
+                )),
+                Err(e) => log::warn!("Skipping invalid entry {entry}: {e}"),
+            }
+        }
+        ranked.sort_by_key(|(result, _, _)| result.xor);
+
+        log::info!(
+            "Ranking {} nodes by distance to 0x{}",
+            ranked.len(),
+            hex::encode(node_a.raw())
+        );
+        for (result, node_id, entry) in ranked {
+            println!(
+                "Bucket {:>3} | shared prefix {:>3} | 0x{} | {}",
+                result.log2.unwrap_or(0),
+                result.shared_prefix,
+                hex::encode(node_id.raw()),
+                entry
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use clap::Args;
+
+use crate::utils::local_node::LocalNode;
+
+/// FindNode
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FindNode {
+    /// The node to send the FINDNODE request to.
+    #[clap(
+        short = 'm',
+        long = "target",
+        allow_hyphen_values = true,
+        help = "The node to query, as an ENR, multiaddr, enode URL or <public-key>@<ip>:<port>"
+    )]
+    pub target: String,
+    /// The log2 distances to request.
+    #[clap(
+        short = 'd',
+        long = "distances",
+        value_delimiter = ',',
+        required = true,
+        help = "A comma separated list of log2 distances to request, e.g. 255,256,0. Distance 0 requests the node's own ENR. Values are sent as given, including invalid distances."
+    )]
+    pub distances: Vec<u64>,
+    /// The local node the request is sent from.
+    #[clap(flatten)]
+    pub local_node: LocalNode,
+}
@@ -0,0 +1,148 @@
+//! Handles sending a single FINDNODE request to a node
+
+use discv5::{
+    enr::{self, NodeId},
+    handler::NodeContact,
+    rpc::{RequestBody, ResponseBody},
+    Key, RequestError,
+};
+
+use crate::{
+    request_enr::target::Target,
+    utils::{local_node::LocalNode, raw_node::RawNode},
+};
+
+/// The [clap] cli command arguments for the findnode service.
+pub mod command;
+pub use command::*;
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The NODES responses to a single FINDNODE request.
+#[derive(Debug, Clone, Default)]
+pub struct NodesResponse {
+    /// The number of NODES packets received.
+    pub packets: u64,
+    /// The number of NODES packets the node announced in its responses.
+    pub total: u64,
+    /// The ENRs of all received packets, in the order they were received.
+    pub enrs: Vec<Enr>,
+}
+
+impl NodesResponse {
+    /// Whether all announced NODES packets were received.
+    pub fn is_complete(&self) -> bool {
+        self.packets >= self.total
+    }
+}
+
+/// Sends a single FINDNODE request for `distances` to the target and collects every NODES
+/// response.
+///
+/// The request bypasses the discv5 service, which only forwards the first NODES packet of a
+/// request to a specific node, and drives the session handler directly instead. The returned
+/// ENRs are not validated against the requested distances.
+pub async fn find_node(
+    local_node: &LocalNode,
+    target: &Target,
+    distances: Vec<u64>,
+) -> eyre::Result<NodesResponse> {
+    let mut node = RawNode::start(local_node, target.is_ipv6()).await?;
+    let contact = target.contact(node.ip_mode()).map_err(|e| eyre::eyre!(e))?;
+    let response = request_nodes(&mut node, contact, distances)
+        .await
+        .map_err(|e| eyre::eyre!("{e}"))?;
+    if !response.is_complete() {
+        log::warn!(
+            "Received {} of {} NODES packets before the request timed out",
+            response.packets,
+            response.total
+        );
+    }
+    Ok(response)
+}
+
+/// Sends a single FINDNODE request for `distances` from a started [`RawNode`] and collects every
+/// NODES response, reusing the session of earlier requests to the same node.
+///
+/// The returned ENRs are not validated against the requested distances.
+pub async fn request_nodes(
+    node: &mut RawNode,
+    contact: NodeContact,
+    distances: Vec<u64>,
+) -> Result<NodesResponse, RequestError> {
+    let packets = node
+        .request(contact, RequestBody::FindNode { distances })
+        .await?;
+
+    let mut response = NodesResponse::default();
+    for packet in packets {
+        match packet {
+            ResponseBody::Nodes { total, nodes } => {
+                log::debug!("Received a NODES packet with {} ENRs", nodes.len());
+                response.packets += 1;
+                response.total = total;
+                response.enrs.extend(nodes);
+            }
+            body => {
+                log::warn!("Unexpected response to a FINDNODE request: {}", body);
+                return Err(RequestError::InvalidRemotePacket);
+            }
+        }
+    }
+    Ok(response)
+}
+
+/// Runs the findnode command. Returns whether the node responded.
+pub async fn run(find_node_cmd: &FindNode) -> bool {
+    let target = match find_node_cmd.target.parse::<Target>() {
+        Ok(target) => target,
+        Err(e) => {
+            log::error!("Invalid target provided. Error: {}", e);
+            return false;
+        }
+    };
+
+    log::info!(
+        "Requesting distances {:?} from {}",
+        find_node_cmd.distances,
+        target
+    );
+    let response = match find_node(
+        &find_node_cmd.local_node,
+        &target,
+        find_node_cmd.distances.clone(),
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            log::error!("FINDNODE request failed. Error: {}", e);
+            return false;
+        }
+    };
+
+    log::info!(
+        "Received {} ENRs in {} of {} NODES packets",
+        response.enrs.len(),
+        response.packets,
+        response.total
+    );
+    let target_key = target.node_id().map(Key::from);
+    for enr in &response.enrs {
+        let node_key = Key::<NodeId>::from(enr.node_id());
+        let distance = match target_key.as_ref().map(|key| key.log2_distance(&node_key)) {
+            Some(Some(distance)) => distance.to_string(),
+            Some(None) => "0".to_string(),
+            None => "?".to_string(),
+        };
+        log::info!(
+            "Node: {} distance: {} seq: {}",
+            enr.node_id(),
+            distance,
+            enr.seq()
+        );
+        println!("{}", enr.to_base64());
+    }
+    true
+}
@@ -0,0 +1,209 @@
+use clap::{Args, Subcommand as ClapSubcommand, ValueEnum};
+
+/// Keys Command
+#[derive(Args, Clone, Debug)]
+pub struct Keys {
+    /// Keys Subcommand
+    #[clap(subcommand)]
+    pub subcommand: KeysSubcommand,
+}
+
+/// Keys Subcommand
+#[derive(ClapSubcommand, Clone, Debug)]
+pub enum KeysSubcommand {
+    /// Generates a new node key.
+    Generate(Generate),
+    /// Prints the public key, node id, peer id and a default ENR of a key.
+    Inspect(Inspect),
+    /// Converts a key between formats.
+    Convert(Convert),
+    /// Generates keys until the node id has a prefix or lies at a distance from a target.
+    Mine(Mine),
+}
+
+/// The type of a node key.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum KeyType {
+    /// A secp256k1 key
+    #[default]
+    Secp256k1,
+    /// An ed25519 key
+    Ed25519,
+}
+
+/// The encoding of a node key.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum KeyFormat {
+    /// The raw secret key as hex
+    #[default]
+    Hex,
+    /// The libp2p protobuf encoded keypair as hex
+    Protobuf,
+    /// A PKCS#8 PEM document
+    Pem,
+    /// An encrypted Ethereum JSON keystore (secp256k1 only)
+    Json,
+}
+
+/// The key to read for inspection or conversion.
+#[derive(Args, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeyInput {
+    /// The encoded key.
+    #[clap(
+        short = 'k',
+        long = "key",
+        required_unless_present = "key_file",
+        conflicts_with = "key_file",
+        help = "The encoded key."
+    )]
+    pub key: Option<String>,
+    /// A file containing the encoded key.
+    #[clap(
+        short = 'f',
+        long = "key-file",
+        help = "A file containing the encoded key."
+    )]
+    pub key_file: Option<String>,
+    /// The format of the key.
+    #[clap(
+        long = "format",
+        help = "The format of the key.",
+        default_value = "hex"
+    )]
+    pub format: KeyFormat,
+    /// The type of a hex encoded key.
+    #[clap(
+        short = 't',
+        long = "key-type",
+        help = "The type of a hex encoded key. Other formats are self-describing.",
+        default_value = "secp256k1"
+    )]
+    pub key_type: KeyType,
+    /// The password of a JSON keystore.
+    #[clap(
+        long = "password",
+        help = "The password used to decrypt or encrypt a JSON keystore."
+    )]
+    pub password: Option<String>,
+}
+
+/// Generate Options
+#[derive(Args, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Generate {
+    /// The type of key to generate.
+    #[clap(
+        short = 't',
+        long = "key-type",
+        help = "The type of key to generate.",
+        default_value = "secp256k1"
+    )]
+    pub key_type: KeyType,
+    /// The format to output the key in.
+    #[clap(
+        long = "to",
+        help = "The format to output the key in.",
+        default_value = "hex"
+    )]
+    pub to: KeyFormat,
+    /// The file to write the key to.
+    #[clap(
+        short = 'o',
+        long = "out",
+        help = "The file to write the key to. The key is printed to stdout if not specified."
+    )]
+    pub out: Option<String>,
+    /// The password of a JSON keystore.
+    #[clap(
+        long = "password",
+        help = "The password used to encrypt a JSON keystore."
+    )]
+    pub password: Option<String>,
+}
+
+/// Inspect Options
+#[derive(Args, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Inspect {
+    /// The key to inspect.
+    #[clap(flatten)]
+    pub input: KeyInput,
+}
+
+/// Convert Options
+#[derive(Args, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Convert {
+    /// The key to convert.
+    #[clap(flatten)]
+    pub input: KeyInput,
+    /// The format to convert the key to.
+    #[clap(long = "to", help = "The format to convert the key to.")]
+    pub to: KeyFormat,
+    /// The file to write the key to.
+    #[clap(
+        short = 'o',
+        long = "out",
+        help = "The file to write the key to. The key is printed to stdout if not specified."
+    )]
+    pub out: Option<String>,
+}
+
+/// Mine Options
+#[derive(Args, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mine {
+    /// The hex prefix the node id must start with.
+    #[clap(
+        long = "prefix",
+        required_unless_present = "target",
+        conflicts_with = "target",
+        help = "The hex prefix the node id must start with."
+    )]
+    pub prefix: Option<String>,
+    /// The node id to mine a key relative to.
+    #[clap(
+        long = "target",
+        requires = "distance",
+        help = "The node (node id, ENR, public key or peer id) the mined node id must lie at --distance from."
+    )]
+    pub target: Option<String>,
+    /// The log2 distance from the target, i.e. the bucket in the target's routing table.
+    #[clap(
+        long = "distance",
+        requires = "target",
+        value_parser = clap::value_parser!(u64).range(1..=256),
+        help = "The log2 distance from the target node id, i.e. the bucket the mined node falls in within the target's routing table."
+    )]
+    pub distance: Option<u64>,
+    /// The type of key to mine.
+    #[clap(
+        short = 't',
+        long = "key-type",
+        help = "The type of key to mine.",
+        default_value = "secp256k1"
+    )]
+    pub key_type: KeyType,
+    /// The number of threads to mine with.
+    #[clap(
+        long = "threads",
+        help = "The number of threads to mine with. Defaults to all available cores."
+    )]
+    pub threads: Option<usize>,
+    /// The format to output the key in.
+    #[clap(
+        long = "to",
+        help = "The format to output the key in.",
+        default_value = "hex"
+    )]
+    pub to: KeyFormat,
+    /// The file to write the key to.
+    #[clap(
+        short = 'o',
+        long = "out",
+        help = "The file to write the key to. The key is printed to stdout if not specified."
+    )]
+    pub out: Option<String>,
+    /// The password of a JSON keystore.
+    #[clap(
+        long = "password",
+        help = "The password used to encrypt a JSON keystore."
+    )]
+    pub password: Option<String>,
+}
@@ -0,0 +1,140 @@
+use std::{fs, path::Path};
+
+use discv5::enr::{k256, CombinedKey};
+use ed25519_dalek::pkcs8::{DecodePrivateKey, EncodePrivateKey};
+use k256::pkcs8::LineEnding;
+use libp2p_identity::Keypair;
+
+use super::command::{KeyFormat, KeyInput, KeyType};
+use crate::utils::enr_ext::CombinedKeyExt;
+
+/// Reads a key in the format given by the cli input.
+pub fn decode(input: &KeyInput) -> eyre::Result<CombinedKey> {
+    // A JSON keystore is decrypted straight from its file.
+    if input.format == KeyFormat::Json {
+        let path = input
+            .key_file
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("A JSON keystore must be read with --key-file"))?;
+        let password = input
+            .password
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("A JSON keystore requires --password"))?;
+        let mut raw_key = eth_keystore::decrypt_key(path, password)
+            .map_err(|e| eyre::eyre!("Failed to decrypt the keystore: {e}"))?;
+        return CombinedKey::secp256k1_from_bytes(&mut raw_key)
+            .map_err(|_| eyre::eyre!("Invalid secp256k1 key in keystore"));
+    }
+
+    let encoded = match (&input.key, &input.key_file) {
+        (Some(key), _) => key.clone(),
+        (None, Some(path)) => fs::read_to_string(path)?,
+        (None, None) => eyre::bail!("Either --key or --key-file must be specified"),
+    };
+    decode_str(encoded.trim(), input.format, input.key_type)
+}
+
+/// Decodes a key from its textual representation.
+pub fn decode_str(
+    encoded: &str,
+    format: KeyFormat,
+    key_type: KeyType,
+) -> eyre::Result<CombinedKey> {
+    match format {
+        KeyFormat::Hex => {
+            let mut raw_key = hex::decode(encoded.trim_start_matches("0x"))
+                .map_err(|_| eyre::eyre!("Invalid hex bytes for key"))?;
+            match key_type {
+                KeyType::Secp256k1 => CombinedKey::secp256k1_from_bytes(&mut raw_key)
+                    .map_err(|_| eyre::eyre!("Invalid secp256k1 key")),
+                KeyType::Ed25519 => CombinedKey::ed25519_from_bytes(&mut raw_key)
+                    .map_err(|_| eyre::eyre!("Invalid ed25519 key")),
+            }
+        }
+        KeyFormat::Protobuf => {
+            let bytes = hex::decode(encoded.trim_start_matches("0x"))
+                .map_err(|_| eyre::eyre!("Invalid hex bytes for protobuf key"))?;
+            let keypair = Keypair::from_protobuf_encoding(&bytes)?;
+            CombinedKey::from_libp2p(&keypair).map_err(|e| eyre::eyre!(e))
+        }
+        KeyFormat::Pem => {
+            if let Ok(secret) = k256::SecretKey::from_pkcs8_pem(encoded) {
+                Ok(CombinedKey::from(k256::ecdsa::SigningKey::from(secret)))
+            } else if let Ok(secret) = k256::SecretKey::from_sec1_pem(encoded) {
+                Ok(CombinedKey::from(k256::ecdsa::SigningKey::from(secret)))
+            } else if let Ok(secret) = ed25519_dalek::SigningKey::from_pkcs8_pem(encoded) {
+                Ok(CombinedKey::from(secret))
+            } else {
+                Err(eyre::eyre!(
+                    "Unsupported PEM key, expected secp256k1 or ed25519"
+                ))
+            }
+        }
+        KeyFormat::Json => Err(eyre::eyre!("A JSON keystore must be read from a file")),
+    }
+}
+
+/// Encodes a key into its textual representation.
+pub fn encode_str(key: &CombinedKey, format: KeyFormat) -> eyre::Result<String> {
+    match format {
+        KeyFormat::Hex => Ok(hex::encode(key.encode())),
+        KeyFormat::Protobuf => {
+            let keypair = key.to_libp2p().map_err(|e| eyre::eyre!(e))?;
+            Ok(hex::encode(keypair.to_protobuf_encoding()?))
+        }
+        KeyFormat::Pem => match key {
+            CombinedKey::Secp256k1(key) => {
+                let secret = k256::SecretKey::from(key.as_nonzero_scalar());
+                Ok(secret.to_pkcs8_pem(LineEnding::LF)?.to_string())
+            }
+            CombinedKey::Ed25519(key) => Ok(key.to_pkcs8_pem(LineEnding::LF)?.to_string()),
+        },
+        KeyFormat::Json => Err(eyre::eyre!("A JSON keystore must be written to a file")),
+    }
+}
+
+/// Writes a key to the given file, or stdout if no file is given.
+pub fn write(
+    key: &CombinedKey,
+    format: KeyFormat,
+    out: Option<&str>,
+    password: Option<&str>,
+) -> eyre::Result<()> {
+    if format == KeyFormat::Json {
+        let CombinedKey::Secp256k1(_) = key else {
+            eyre::bail!("A JSON keystore can only hold secp256k1 keys");
+        };
+        let path = Path::new(out.ok_or_else(|| eyre::eyre!("A JSON keystore requires --out"))?);
+        let password =
+            password.ok_or_else(|| eyre::eyre!("A JSON keystore requires --password"))?;
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| eyre::eyre!("Invalid keystore file name"))?;
+        fs::create_dir_all(dir)?;
+        eth_keystore::encrypt_key(
+            dir,
+            &mut rand::thread_rng(),
+            key.encode(),
+            password,
+            Some(name),
+        )
+        .map_err(|e| eyre::eyre!("Failed to encrypt the keystore: {e}"))?;
+        log::info!("Keystore written to {}", path.display());
+        return Ok(());
+    }
+
+    let encoded = encode_str(key, format)?;
+    match out {
+        Some(path) => {
+            fs::write(path, &encoded)?;
+            log::info!("Key written to {path}");
+        }
+        None => println!("{}", encoded.trim_end()),
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use discv5::{
+    enr::{CombinedKey, EnrKey, NodeId},
+    Key,
+};
+
+use super::command::KeyType;
+
+/// How often the mining progress is logged.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The condition a mined node id must satisfy.
+#[derive(Debug, Clone)]
+pub enum Criteria {
+    /// The lowercase hex encoded node id starts with the prefix.
+    Prefix(String),
+    /// The node id lies at the log2 distance from the target, i.e. it falls into that bucket of
+    /// the target's routing table.
+    Distance {
+        /// The node id the distance is measured from.
+        target: NodeId,
+        /// The log2 distance.
+        distance: u64,
+    },
+}
+
+impl Criteria {
+    /// Returns true if the node id satisfies the criteria.
+    pub fn matches(&self, node_id: &NodeId) -> bool {
+        match self {
+            Criteria::Prefix(prefix) => {
+                let bytes = &node_id.raw()[..prefix.len().div_ceil(2)];
+                hex::encode(bytes).starts_with(prefix.as_str())
+            }
+            Criteria::Distance { target, distance } => {
+                let key: Key<NodeId> = (*node_id).into();
+                key.log2_distance(&(*target).into()) == Some(*distance)
+            }
+        }
+    }
+
+    /// The expected number of keys to generate before a match is found.
+    pub fn expected_attempts(&self) -> f64 {
+        match self {
+            Criteria::Prefix(prefix) => 16f64.powi(prefix.len() as i32),
+            Criteria::Distance { distance, .. } => 2f64.powi(257 - *distance as i32),
+        }
+    }
+}
+
+/// Generates keys on `threads` threads until one satisfies the criteria. Returns the key and the
+/// total number of keys generated.
+pub fn mine(criteria: &Criteria, key_type: KeyType, threads: usize) -> (CombinedKey, u64) {
+    let found = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicU64::new(0));
+    let (sender, receiver) = mpsc::channel();
+
+    for _ in 0..threads.max(1) {
+        let criteria = criteria.clone();
+        let found = Arc::clone(&found);
+        let attempts = Arc::clone(&attempts);
+        let sender = sender.clone();
+        thread::spawn(move || {
+            while !found.load(Ordering::Relaxed) {
+                let key = match key_type {
+                    KeyType::Secp256k1 => CombinedKey::generate_secp256k1(),
+                    KeyType::Ed25519 => CombinedKey::generate_ed25519(),
+                };
+                attempts.fetch_add(1, Ordering::Relaxed);
+                let node_id = NodeId::from(key.public());
+                if criteria.matches(&node_id) && !found.swap(true, Ordering::Relaxed) {
+                    let _ = sender.send(key);
+                }
+            }
+        });
+    }
+
+    let start = Instant::now();
+    loop {
+        match receiver.recv_timeout(PROGRESS_INTERVAL) {
+            Ok(key) => return (key, attempts.load(Ordering::Relaxed)),
+            Err(_) => {
+                let attempts = attempts.load(Ordering::Relaxed);
+                log::info!(
+                    "Generated {} keys ({:.0} keys/s)",
+                    attempts,
+                    attempts as f64 / start.elapsed().as_secs_f64()
+                );
+            }
+        }
+    }
+}
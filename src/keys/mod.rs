@@ -0,0 +1,101 @@
+//! Handles the key generation, inspection and conversion functions
+
+use discv5::enr::{self, CombinedKey, EnrPublicKey};
+
+use crate::utils::enr_ext::EnrExt;
+
+/// The [clap] cli command arguments for the keys service.
+pub mod command;
+pub use command::*;
+
+/// Encoding and decoding of keys in the supported formats.
+pub mod format;
+
+/// Multi-threaded mining of keys with targeted node ids.
+pub mod mine;
+
+/// Generates a new key, prints its details and outputs it in the requested format.
+pub fn generate(generate: &Generate) -> eyre::Result<()> {
+    let key = match generate.key_type {
+        KeyType::Secp256k1 => CombinedKey::generate_secp256k1(),
+        KeyType::Ed25519 => CombinedKey::generate_ed25519(),
+    };
+    print_key_info(&key)?;
+    format::write(
+        &key,
+        generate.to,
+        generate.out.as_deref(),
+        generate.password.as_deref(),
+    )
+}
+
+/// Prints the details of an existing key.
+pub fn inspect(inspect: &Inspect) -> eyre::Result<()> {
+    let key = format::decode(&inspect.input)?;
+    print_key_info(&key)
+}
+
+/// Converts a key into another format.
+pub fn convert(convert: &Convert) -> eyre::Result<()> {
+    let key = format::decode(&convert.input)?;
+    format::write(
+        &key,
+        convert.to,
+        convert.out.as_deref(),
+        convert.input.password.as_deref(),
+    )
+}
+
+/// Mines a key whose node id has the requested prefix or log2 distance to a target.
+pub fn mine(mine: &Mine) -> eyre::Result<()> {
+    let criteria = match (&mine.prefix, &mine.target, mine.distance) {
+        (Some(prefix), _, _) => {
+            let prefix = prefix.trim_start_matches("0x").to_lowercase();
+            if prefix.len() > 64 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+                eyre::bail!("Invalid hex prefix: {prefix}");
+            }
+            mine::Criteria::Prefix(prefix)
+        }
+        (None, Some(target), Some(distance)) => {
+            let target = crate::utils::node_id::parse(target)?;
+            mine::Criteria::Distance { target, distance }
+        }
+        _ => eyre::bail!("Either --prefix or --target and --distance must be specified"),
+    };
+
+    let threads = match mine.threads {
+        Some(threads) => threads,
+        None => std::thread::available_parallelism()?.get(),
+    };
+    log::info!(
+        "Mining for {:?} on {} threads, expecting ~{:.0} attempts",
+        criteria,
+        threads,
+        criteria.expected_attempts()
+    );
+
+    let start = std::time::Instant::now();
+    let (key, attempts) = mine::mine(&criteria, mine.key_type, threads);
+    log::info!(
+        "Found a matching key after {} attempts in {:.1?}",
+        attempts,
+        start.elapsed()
+    );
+    print_key_info(&key)?;
+    format::write(&key, mine.to, mine.out.as_deref(), mine.password.as_deref())
+}
+
+/// Prints the public key, node id, peer id and default ENR of a key.
+pub fn print_key_info(key: &CombinedKey) -> eyre::Result<()> {
+    let enr = enr::Enr::builder().build(key)?;
+    let key_type = match key {
+        CombinedKey::Secp256k1(_) => "secp256k1",
+        CombinedKey::Ed25519(_) => "ed25519",
+    };
+    log::info!("Key type: {}", key_type);
+    log::info!("Public key: 0x{}", hex::encode(enr.public_key().encode()));
+    log::info!("Node Id: 0x{}", hex::encode(enr.node_id().raw()));
+    log::info!("Libp2p PeerId: {}", enr.peer_id());
+    log::info!("Default ENR: {}", enr.to_base64());
+    Ok(())
+}
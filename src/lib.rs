@@ -7,14 +7,35 @@
 /// Cli Handlers
 pub mod cli;
 
+/// Conformance Handlers
+pub mod conformance;
+
+/// Distance Handlers
+pub mod distance;
+
+/// FindNode Handlers
+pub mod findnode;
+
+/// Key Handlers
+pub mod keys;
+
 /// Packet Handlers
 pub mod packet;
 
+/// Ping Handlers
+pub mod ping;
+
+/// Portal Handlers
+pub mod portal;
+
 /// Request ENR Handlers
 pub mod request_enr;
 
 /// Server Handlers
 pub mod server;
 
+/// Talk Handlers
+pub mod talk;
+
 /// Utility functions
 pub mod utils;
@@ -1,12 +1,11 @@
 #![doc=include_str!("../README.md")]
 
-use clap::Parser;
-use discv5_cli::{cli, packet};
+use discv5_cli::{cli, distance, keys, packet, portal};
 
 #[tokio::main]
 async fn main() {
-    // Parse the command line arguments
-    let cli = cli::Cli::parse();
+    // Parse the command line arguments and any server configuration file
+    let cli = cli::Cli::parse_with_config();
 
     // Setup logging using the specified log level
     discv5_cli::utils::logging::construct_simple_logger(cli.log_level);
@@ -16,14 +15,69 @@ async fn main() {
         Some(cli::Subcommand::Server(ref s)) => {
             discv5_cli::server::run(s).await;
         }
+        Some(cli::Subcommand::Ping(ref ping)) => {
+            if !discv5_cli::ping::run(ping).await {
+                std::process::exit(1);
+            }
+        }
+        Some(cli::Subcommand::FindNode(ref find_node)) => {
+            if !discv5_cli::findnode::run(find_node).await {
+                std::process::exit(1);
+            }
+        }
         Some(cli::Subcommand::RequestEnr(ref request_enr)) => {
-            discv5_cli::request_enr::run(request_enr).await;
+            if !discv5_cli::request_enr::run(request_enr).await {
+                std::process::exit(1);
+            }
+        }
+        Some(cli::Subcommand::Talk(ref talk)) => {
+            if !discv5_cli::talk::run(talk).await {
+                std::process::exit(1);
+            }
+        }
+        Some(cli::Subcommand::Portal(portal::Portal { subcommand })) => {
+            let result = match subcommand {
+                portal::PortalSubcommand::Ping(ref ping) => portal::ping(ping).await,
+                portal::PortalSubcommand::FindNodes(ref find_nodes) => {
+                    portal::find_nodes(find_nodes).await
+                }
+                portal::PortalSubcommand::FindContent(ref find_content) => {
+                    portal::find_content(find_content).await
+                }
+            };
+            if let Err(e) = result {
+                log::error!("Portal request failed. Error: {}", e);
+                std::process::exit(1);
+            }
         }
         Some(cli::Subcommand::Packet(packet::Packet { subcommand })) => match subcommand {
             packet::PacketSubcommand::Decode(ref decode) => {
                 packet::decode(decode);
             }
         },
+        Some(cli::Subcommand::Conformance(ref conformance)) => {
+            if !discv5_cli::conformance::run(conformance).await {
+                std::process::exit(1);
+            }
+        }
+        Some(cli::Subcommand::Distance(ref d)) => {
+            if let Err(e) = distance::run(d) {
+                log::error!("Failed to calculate the distance. Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(cli::Subcommand::Keys(keys::Keys { subcommand })) => {
+            let result = match subcommand {
+                keys::KeysSubcommand::Generate(ref generate) => keys::generate(generate),
+                keys::KeysSubcommand::Inspect(ref inspect) => keys::inspect(inspect),
+                keys::KeysSubcommand::Convert(ref convert) => keys::convert(convert),
+                keys::KeysSubcommand::Mine(ref mine) => keys::mine(mine),
+            };
+            if let Err(e) = result {
+                log::error!("Key operation failed. Error: {}", e);
+                std::process::exit(1);
+            }
+        }
         _ => log::error!("Unable to parse command line arguments. See --help for options"),
     }
 }
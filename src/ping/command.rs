@@ -0,0 +1,35 @@
+use clap::Args;
+
+use crate::utils::local_node::LocalNode;
+
+/// Ping
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ping {
+    /// The node to ping.
+    #[clap(
+        short = 'm',
+        long = "target",
+        allow_hyphen_values = true,
+        help = "The node to ping, as a multiaddr, ENR, enode URL or <public-key>@<ip>:<port>"
+    )]
+    pub target: String,
+    /// The number of pings to send.
+    #[clap(
+        short = 'c',
+        long = "count",
+        help = "The number of pings to send.",
+        default_value = "5"
+    )]
+    pub count: u64,
+    /// The time to wait between pings in milliseconds.
+    #[clap(
+        short = 'i',
+        long = "interval",
+        help = "The time to wait between pings in milliseconds.",
+        default_value = "1000"
+    )]
+    pub interval: u64,
+    /// The local node the pings are sent from.
+    #[clap(flatten)]
+    pub local_node: LocalNode,
+}
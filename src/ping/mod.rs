@@ -0,0 +1,200 @@
+//! Handles pinging a node
+
+use std::{
+    collections::BTreeSet,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use discv5::{enr, Discv5};
+
+use crate::request_enr::target::Target;
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The [clap] cli command arguments for the ping service.
+pub mod command;
+pub use command::*;
+
+/// Round-trip time statistics in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RttStatistics {
+    /// The fastest round-trip time.
+    pub min: f64,
+    /// The mean round-trip time.
+    pub avg: f64,
+    /// The slowest round-trip time.
+    pub max: f64,
+    /// The population standard deviation of the round-trip times.
+    pub stddev: f64,
+}
+
+impl RttStatistics {
+    /// Calculates the statistics of the round-trip times. Returns `None` if there are none.
+    pub fn from_rtts(rtts: &[f64]) -> Option<Self> {
+        if rtts.is_empty() {
+            return None;
+        }
+        let count = rtts.len() as f64;
+        let avg = rtts.iter().sum::<f64>() / count;
+        let variance = rtts.iter().map(|rtt| (rtt - avg).powi(2)).sum::<f64>() / count;
+        Some(RttStatistics {
+            min: rtts.iter().cloned().fold(f64::INFINITY, f64::min),
+            avg,
+            max: rtts.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            stddev: variance.sqrt(),
+        })
+    }
+}
+
+/// The outcome of pinging a node.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PingReport {
+    /// The number of pings sent.
+    pub sent: u64,
+    /// The round-trip time of the ping that established the session, which includes the
+    /// handshake.
+    pub handshake_rtt: Option<f64>,
+    /// The round-trip times of the pings sent over an established session.
+    pub rtts: Vec<f64>,
+    /// The addresses the node observed us at.
+    pub observed: BTreeSet<SocketAddr>,
+}
+
+impl PingReport {
+    /// The number of pongs received.
+    pub fn received(&self) -> u64 {
+        self.rtts.len() as u64 + u64::from(self.handshake_rtt.is_some())
+    }
+}
+
+/// Sends `count` pings to the node, `interval` apart, until done or interrupted with CTRL-C.
+///
+/// If `handshake` is set there is no session with the node yet, and the round-trip time of the
+/// first answered ping is kept apart from the others as it includes the session handshake.
+pub async fn ping(
+    discv5: &Discv5,
+    enr: &Enr,
+    count: u64,
+    interval: Duration,
+    handshake: bool,
+) -> PingReport {
+    let mut report = PingReport::default();
+    let mut session = !handshake;
+    let mut interval = tokio::time::interval(interval);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    while report.sent < count {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = &mut ctrl_c => break,
+        }
+        report.sent += 1;
+        let start = Instant::now();
+        let result = tokio::select! {
+            result = discv5.send_ping(enr.clone()) => result,
+            _ = &mut ctrl_c => {
+                log::info!("Ping {} interrupted", report.sent);
+                break;
+            }
+        };
+        match result {
+            Ok(pong) => {
+                let rtt = start.elapsed().as_secs_f64() * 1000.0;
+                let observed_address = SocketAddr::new(pong.ip, pong.port);
+                log::info!(
+                    "Pong {}: time={:.2}ms{} enr_seq={} observed_address={}",
+                    report.sent,
+                    rtt,
+                    if session { "" } else { " (with handshake)" },
+                    pong.enr_seq,
+                    observed_address
+                );
+                if session {
+                    report.rtts.push(rtt);
+                } else {
+                    report.handshake_rtt = Some(rtt);
+                    session = true;
+                }
+                report.observed.insert(observed_address);
+            }
+            Err(e) => log::warn!("Ping {} failed. Error: {}", report.sent, e),
+        }
+    }
+    report
+}
+
+/// Runs the ping command. Returns whether any PONG was received.
+pub async fn run(ping_cmd: &Ping) -> bool {
+    let target = match ping_cmd.target.parse::<Target>() {
+        Ok(target) => target,
+        Err(e) => {
+            log::error!("Invalid target provided. Error: {}", e);
+            return false;
+        }
+    };
+
+    // Retries would hide packet loss and inflate the round-trip times.
+    let discv5 = match ping_cmd
+        .local_node
+        .start(target.is_ipv6(), |config| {
+            config.request_retries(0);
+        })
+        .await
+    {
+        Ok(discv5) => discv5,
+        Err(e) => {
+            log::error!("Failed to start the local node. Error: {}", e);
+            return false;
+        }
+    };
+
+    let enr = match target.resolve(&discv5).await {
+        Ok(enr) => enr,
+        Err(e) => {
+            log::error!("Failed to obtain the ENR of the target. Error: {}", e);
+            return false;
+        }
+    };
+    log::info!(
+        "Pinging {} (ENR seq {}) {} times",
+        enr.node_id(),
+        enr.seq(),
+        ping_cmd.count
+    );
+
+    // Requesting the ENR of a multiaddr target already established the session
+    let handshake = !matches!(target, Target::Multiaddr(_));
+    let report = ping(
+        &discv5,
+        &enr,
+        ping_cmd.count,
+        Duration::from_millis(ping_cmd.interval),
+        handshake,
+    )
+    .await;
+
+    let received = report.received();
+    log::info!(
+        "{} pings sent, {} received, {:.1}% loss",
+        report.sent,
+        received,
+        report.sent.saturating_sub(received) as f64 * 100.0 / report.sent.max(1) as f64
+    );
+    if let Some(rtt) = report.handshake_rtt {
+        log::info!("rtt including the session handshake = {:.2} ms", rtt);
+    }
+    if let Some(stats) = RttStatistics::from_rtts(&report.rtts) {
+        log::info!(
+            "rtt min/avg/max/stddev = {:.2}/{:.2}/{:.2}/{:.2} ms",
+            stats.min,
+            stats.avg,
+            stats.max,
+            stats.stddev
+        );
+    }
+    for address in &report.observed {
+        log::info!("Peer observed us at: {}", address);
+    }
+    received > 0
+}
@@ -0,0 +1,115 @@
+use clap::{Args, Subcommand as ClapSubcommand, ValueEnum};
+
+use crate::utils::local_node::LocalNode;
+
+/// Portal Command
+#[derive(Args, Clone, Debug)]
+pub struct Portal {
+    /// Portal Subcommand
+    #[clap(subcommand)]
+    pub subcommand: PortalSubcommand,
+}
+
+/// Portal Subcommand
+#[derive(ClapSubcommand, Clone, Debug)]
+pub enum PortalSubcommand {
+    /// Sends a PING and prints the PONG.
+    Ping(PortalPing),
+    /// Requests the nodes at log2 distances in the node's sub-network routing table.
+    FindNodes(PortalFindNodes),
+    /// Requests a piece of content, or the nodes closest to it.
+    FindContent(PortalFindContent),
+}
+
+/// A Portal sub-network.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Network {
+    /// The state network
+    State,
+    /// The history network
+    #[default]
+    History,
+    /// The beacon chain network
+    Beacon,
+}
+
+impl Network {
+    /// The mainnet TALK protocol id of the network.
+    pub fn protocol_id(&self) -> [u8; 2] {
+        match self {
+            Network::State => [0x50, 0x0a],
+            Network::History => [0x50, 0x0b],
+            Network::Beacon => [0x50, 0x0c],
+        }
+    }
+}
+
+/// The node and sub-network a Portal request is sent to.
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PortalTarget {
+    /// The node to send the request to.
+    #[clap(
+        short = 'm',
+        long = "target",
+        allow_hyphen_values = true,
+        help = "The node to send the request to, as an ENR, multiaddr, enode URL or <public-key>@<ip>:<port>"
+    )]
+    pub target: String,
+    /// The sub-network to send the request on.
+    #[clap(
+        short = 'n',
+        long = "network",
+        help = "The sub-network to send the request on.",
+        default_value = "history"
+    )]
+    pub network: Network,
+    /// A protocol id overriding the network's mainnet protocol id.
+    #[clap(
+        long = "protocol-id",
+        help = "A hex encoded TALK protocol id overriding the mainnet protocol id of the network, e.g. for testnets."
+    )]
+    pub protocol_id: Option<String>,
+    /// The local node the request is sent from.
+    #[clap(flatten)]
+    pub local_node: LocalNode,
+}
+
+/// Portal Ping Options
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PortalPing {
+    /// The node and sub-network to ping.
+    #[clap(flatten)]
+    pub target: PortalTarget,
+}
+
+/// Portal FindNodes Options
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PortalFindNodes {
+    /// The node and sub-network to query.
+    #[clap(flatten)]
+    pub target: PortalTarget,
+    /// The log2 distances to request.
+    #[clap(
+        short = 'd',
+        long = "distances",
+        value_delimiter = ',',
+        required = true,
+        help = "A comma separated list of log2 distances to request, e.g. 255,256,0. Distance 0 requests the node's own ENR."
+    )]
+    pub distances: Vec<u16>,
+}
+
+/// Portal FindContent Options
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PortalFindContent {
+    /// The node and sub-network to query.
+    #[clap(flatten)]
+    pub target: PortalTarget,
+    /// The content key to request.
+    #[clap(
+        short = 'c',
+        long = "content-key",
+        help = "The hex encoded, sub-network specific content key."
+    )]
+    pub content_key: String,
+}
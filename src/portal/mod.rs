@@ -0,0 +1,202 @@
+//! Handles requests to Portal network nodes over TALK
+
+use std::time::Instant;
+
+use discv5::{
+    enr::{self, CombinedKey},
+    Discv5,
+};
+
+use crate::request_enr::target::Target;
+
+/// The [clap] cli command arguments for the portal service.
+pub mod command;
+pub use command::*;
+
+/// Encoding and decoding of the Portal wire protocol messages.
+pub mod wire;
+use wire::{Content, Message, PingPayload};
+
+/// The client info sent in our PING payload.
+const CLIENT_INFO: &str = concat!("discv5-cli/v", env!("CARGO_PKG_VERSION"));
+
+/// A local node connected to a Portal node.
+struct Session {
+    discv5: Discv5,
+    enr: enr::Enr<CombinedKey>,
+    protocol: Vec<u8>,
+}
+
+impl Session {
+    /// Starts a local node and obtains the ENR of the target.
+    async fn connect(target: &PortalTarget) -> eyre::Result<Self> {
+        let protocol = match &target.protocol_id {
+            Some(protocol_id) => hex::decode(protocol_id.trim_start_matches("0x"))
+                .map_err(|_| eyre::eyre!("Invalid hex protocol id {protocol_id}"))?,
+            None => target.network.protocol_id().to_vec(),
+        };
+        let node = target
+            .target
+            .parse::<Target>()
+            .map_err(|e| eyre::eyre!("Invalid target provided: {e}"))?;
+        let discv5 = target
+            .local_node
+            .start(node.is_ipv6(), |_| {})
+            .await
+            .map_err(|e| eyre::eyre!("Failed to start the local node: {e}"))?;
+        let enr = node
+            .resolve(&discv5)
+            .await
+            .map_err(|e| eyre::eyre!("Failed to obtain the ENR of the target: {e}"))?;
+        Ok(Session {
+            discv5,
+            enr,
+            protocol,
+        })
+    }
+
+    /// Sends a message and decodes the response.
+    async fn request(&self, message: Message) -> eyre::Result<Message> {
+        log::info!(
+            "Sending {} on protocol 0x{} to {}",
+            message,
+            hex::encode(&self.protocol),
+            self.enr.node_id()
+        );
+        let start = Instant::now();
+        let response = self
+            .discv5
+            .talk_req(self.enr.clone(), self.protocol.clone(), message.encode())
+            .await
+            .map_err(|e| eyre::eyre!("TALKREQ failed: {e}"))?;
+        log::info!(
+            "Received {} bytes in {:.2}ms",
+            response.len(),
+            start.elapsed().as_secs_f64() * 1000.0
+        );
+        if response.is_empty() {
+            eyre::bail!("The response is empty, the node does not support the sub-network");
+        }
+        Message::decode(&response)
+            .map_err(|e| eyre::eyre!("Invalid response 0x{}: {e}", hex::encode(&response)))
+    }
+}
+
+/// Sends a PING and prints the PONG.
+pub async fn ping(ping: &PortalPing) -> eyre::Result<()> {
+    let session = Session::connect(&ping.target).await?;
+    // Every node supports the capabilities payload
+    let payload = PingPayload::Capabilities {
+        client_info: CLIENT_INFO.as_bytes().to_vec(),
+        data_radius: [0; 32],
+        capabilities: vec![wire::CAPABILITIES_PAYLOAD],
+    };
+    let request = Message::Ping {
+        enr_seq: session.discv5.local_enr().seq(),
+        payload_type: payload.payload_type(),
+        payload: payload.encode(),
+    };
+    match session.request(request).await? {
+        Message::Pong {
+            enr_seq,
+            payload_type,
+            payload,
+        } => {
+            log::info!("PONG enr_seq: {}", enr_seq);
+            match PingPayload::decode(payload_type, &payload) {
+                Ok(payload) => print_ping_payload(&payload),
+                Err(e) => log::warn!(
+                    "Payload type {}: 0x{} could not be decoded. Error: {}",
+                    payload_type,
+                    hex::encode(&payload),
+                    e
+                ),
+            }
+            Ok(())
+        }
+        response => Err(eyre::eyre!("Unexpected response: {response}")),
+    }
+}
+
+/// Sends a FINDNODES request and prints the returned ENRs.
+pub async fn find_nodes(find_nodes: &PortalFindNodes) -> eyre::Result<()> {
+    let session = Session::connect(&find_nodes.target).await?;
+    let request = Message::FindNodes {
+        distances: find_nodes.distances.clone(),
+    };
+    match session.request(request).await? {
+        Message::Nodes { total, enrs } => {
+            log::info!("NODES total: {} enrs: {}", total, enrs.len());
+            print_enrs(&enrs);
+            Ok(())
+        }
+        response => Err(eyre::eyre!("Unexpected response: {response}")),
+    }
+}
+
+/// Sends a FINDCONTENT request and prints the content or the returned ENRs.
+pub async fn find_content(find_content: &PortalFindContent) -> eyre::Result<()> {
+    let content_key = hex::decode(find_content.content_key.trim_start_matches("0x"))
+        .map_err(|_| eyre::eyre!("Invalid hex content key"))?;
+    let session = Session::connect(&find_content.target).await?;
+    match session
+        .request(Message::FindContent { content_key })
+        .await?
+    {
+        Message::Content(Content::Content(content)) => {
+            log::info!("CONTENT with {} bytes", content.len());
+            println!("0x{}", hex::encode(content));
+        }
+        Message::Content(Content::ConnectionId(connection_id)) => {
+            log::info!(
+                "CONTENT is too large for a single packet, offered over uTP with connection id 0x{}. uTP transfers are not supported.",
+                hex::encode(connection_id)
+            );
+        }
+        Message::Content(Content::Enrs(enrs)) => {
+            log::info!("CONTENT not found, {} closer nodes returned", enrs.len());
+            print_enrs(&enrs);
+        }
+        response => eyre::bail!("Unexpected response: {response}"),
+    }
+    Ok(())
+}
+
+/// Prints the fields of a PING or PONG payload.
+fn print_ping_payload(payload: &PingPayload) {
+    match payload {
+        PingPayload::Capabilities {
+            client_info,
+            data_radius,
+            capabilities,
+        } => {
+            log::info!("Client info: {}", String::from_utf8_lossy(client_info));
+            log::info!("Data radius: {}", wire::format_radius(data_radius));
+            log::info!("Capabilities: {:?}", capabilities);
+        }
+        PingPayload::BasicRadius { data_radius } => {
+            log::info!("Data radius: {}", wire::format_radius(data_radius));
+        }
+        PingPayload::HistoryRadius {
+            data_radius,
+            ephemeral_header_count,
+        } => {
+            log::info!("Data radius: {}", wire::format_radius(data_radius));
+            log::info!("Ephemeral headers: {}", ephemeral_header_count);
+        }
+        PingPayload::Error {
+            error_code,
+            message,
+        } => {
+            log::warn!("Error {}: {}", error_code, String::from_utf8_lossy(message));
+        }
+    }
+}
+
+/// Prints ENRs to stdout, one per line.
+fn print_enrs(enrs: &[enr::Enr<CombinedKey>]) {
+    for enr in enrs {
+        log::info!("Node: {} seq: {}", enr.node_id(), enr.seq());
+        println!("{}", enr.to_base64());
+    }
+}
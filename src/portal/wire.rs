@@ -0,0 +1,436 @@
+use std::{convert::TryInto, fmt};
+
+use alloy_rlp::Decodable;
+use discv5::enr;
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The size of an SSZ offset of a variable sized field.
+const OFFSET_LENGTH: usize = 4;
+
+/// The PING and PONG payload type of the client info, radius and capabilities payload.
+pub const CAPABILITIES_PAYLOAD: u16 = 0;
+/// The PING and PONG payload type of the basic radius payload.
+pub const BASIC_RADIUS_PAYLOAD: u16 = 1;
+/// The PING and PONG payload type of the history radius payload.
+pub const HISTORY_RADIUS_PAYLOAD: u16 = 2;
+/// The PONG payload type of an error.
+pub const ERROR_PAYLOAD: u16 = 65535;
+
+/// A Portal wire protocol message, sent as the payload of a TALKREQ or TALKRESP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// Requests the sender's ENR sequence number and a custom payload.
+    Ping {
+        /// The ENR sequence number of the sender.
+        enr_seq: u64,
+        /// The type of the payload.
+        payload_type: u16,
+        /// The SSZ encoded payload.
+        payload: Vec<u8>,
+    },
+    /// The response to a PING.
+    Pong {
+        /// The ENR sequence number of the sender.
+        enr_seq: u64,
+        /// The type of the payload.
+        payload_type: u16,
+        /// The SSZ encoded payload.
+        payload: Vec<u8>,
+    },
+    /// Requests the nodes at the log2 distances in the sub-network's routing table.
+    FindNodes {
+        /// The requested log2 distances.
+        distances: Vec<u16>,
+    },
+    /// The response to a FINDNODES.
+    Nodes {
+        /// The number of NODES messages sent in response to the request.
+        total: u8,
+        /// The returned ENRs.
+        enrs: Vec<Enr>,
+    },
+    /// Requests a piece of content, or the nodes closest to it.
+    FindContent {
+        /// The sub-network specific content key.
+        content_key: Vec<u8>,
+    },
+    /// The response to a FINDCONTENT.
+    Content(Content),
+}
+
+/// The payload of a CONTENT message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Content {
+    /// The content is too large for a single packet and has to be fetched over uTP with this
+    /// connection id.
+    ConnectionId([u8; 2]),
+    /// The requested content.
+    Content(Vec<u8>),
+    /// The content is unknown, these are the nodes closest to it.
+    Enrs(Vec<Enr>),
+}
+
+impl Message {
+    /// The message id that prefixes the SSZ encoded message.
+    pub fn id(&self) -> u8 {
+        match self {
+            Message::Ping { .. } => 0x00,
+            Message::Pong { .. } => 0x01,
+            Message::FindNodes { .. } => 0x02,
+            Message::Nodes { .. } => 0x03,
+            Message::FindContent { .. } => 0x04,
+            Message::Content(_) => 0x05,
+        }
+    }
+
+    /// Encodes the message id followed by the SSZ encoded message.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![self.id()];
+        match self {
+            Message::Ping {
+                enr_seq,
+                payload_type,
+                payload,
+            }
+            | Message::Pong {
+                enr_seq,
+                payload_type,
+                payload,
+            } => {
+                bytes.extend_from_slice(&enr_seq.to_le_bytes());
+                bytes.extend_from_slice(&payload_type.to_le_bytes());
+                bytes.extend_from_slice(&offset(8 + 2 + OFFSET_LENGTH));
+                bytes.extend_from_slice(payload);
+            }
+            Message::FindNodes { distances } => {
+                bytes.extend_from_slice(&offset(OFFSET_LENGTH));
+                for distance in distances {
+                    bytes.extend_from_slice(&distance.to_le_bytes());
+                }
+            }
+            Message::Nodes { total, enrs } => {
+                bytes.push(*total);
+                bytes.extend_from_slice(&offset(1 + OFFSET_LENGTH));
+                bytes.extend_from_slice(&encode_enrs(enrs));
+            }
+            Message::FindContent { content_key } => {
+                bytes.extend_from_slice(&offset(OFFSET_LENGTH));
+                bytes.extend_from_slice(content_key);
+            }
+            Message::Content(Content::ConnectionId(connection_id)) => {
+                bytes.push(0);
+                bytes.extend_from_slice(connection_id);
+            }
+            Message::Content(Content::Content(content)) => {
+                bytes.push(1);
+                bytes.extend_from_slice(content);
+            }
+            Message::Content(Content::Enrs(enrs)) => {
+                bytes.push(2);
+                bytes.extend_from_slice(&encode_enrs(enrs));
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a message id prefixed SSZ encoded message.
+    pub fn decode(bytes: &[u8]) -> eyre::Result<Self> {
+        let (id, body) = bytes
+            .split_first()
+            .ok_or_else(|| eyre::eyre!("Empty message"))?;
+        match id {
+            0x00 | 0x01 => {
+                let fixed = 8 + 2 + OFFSET_LENGTH;
+                if body.len() < fixed {
+                    eyre::bail!("Message too short");
+                }
+                let enr_seq = u64::from_le_bytes(body[..8].try_into().expect("8 bytes"));
+                let payload_type = u16::from_le_bytes(body[8..10].try_into().expect("2 bytes"));
+                let payload = variable_field(body, 10, fixed)?.to_vec();
+                Ok(if *id == 0x00 {
+                    Message::Ping {
+                        enr_seq,
+                        payload_type,
+                        payload,
+                    }
+                } else {
+                    Message::Pong {
+                        enr_seq,
+                        payload_type,
+                        payload,
+                    }
+                })
+            }
+            0x02 => {
+                let distances = variable_field(body, 0, OFFSET_LENGTH)?;
+                if !distances.len().is_multiple_of(2) {
+                    eyre::bail!("Invalid distances length");
+                }
+                Ok(Message::FindNodes {
+                    distances: distances
+                        .chunks_exact(2)
+                        .map(|distance| u16::from_le_bytes([distance[0], distance[1]]))
+                        .collect(),
+                })
+            }
+            0x03 => {
+                let total = *body
+                    .first()
+                    .ok_or_else(|| eyre::eyre!("Message too short"))?;
+                let enrs = decode_enrs(variable_field(body, 1, 1 + OFFSET_LENGTH)?)?;
+                Ok(Message::Nodes { total, enrs })
+            }
+            0x04 => Ok(Message::FindContent {
+                content_key: variable_field(body, 0, OFFSET_LENGTH)?.to_vec(),
+            }),
+            0x05 => {
+                let (selector, value) = body
+                    .split_first()
+                    .ok_or_else(|| eyre::eyre!("Message too short"))?;
+                let content = match selector {
+                    0 => Content::ConnectionId(
+                        value
+                            .try_into()
+                            .map_err(|_| eyre::eyre!("Invalid connection id"))?,
+                    ),
+                    1 => Content::Content(value.to_vec()),
+                    2 => Content::Enrs(decode_enrs(value)?),
+                    selector => eyre::bail!("Unknown content selector {selector}"),
+                };
+                Ok(Message::Content(content))
+            }
+            id => eyre::bail!("Unsupported message id {id:#04x}"),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Ping { .. } => write!(f, "PING"),
+            Message::Pong { .. } => write!(f, "PONG"),
+            Message::FindNodes { .. } => write!(f, "FINDNODES"),
+            Message::Nodes { .. } => write!(f, "NODES"),
+            Message::FindContent { .. } => write!(f, "FINDCONTENT"),
+            Message::Content(_) => write!(f, "CONTENT"),
+        }
+    }
+}
+
+/// A decoded PING or PONG payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PingPayload {
+    /// The client info, data radius and supported payload types of the node.
+    Capabilities {
+        /// A free form description of the client.
+        client_info: Vec<u8>,
+        /// The data radius as a little endian uint256.
+        data_radius: [u8; 32],
+        /// The supported payload types.
+        capabilities: Vec<u16>,
+    },
+    /// The data radius of the node.
+    BasicRadius {
+        /// The data radius as a little endian uint256.
+        data_radius: [u8; 32],
+    },
+    /// The data radius and the number of ephemeral headers the node stores.
+    HistoryRadius {
+        /// The data radius as a little endian uint256.
+        data_radius: [u8; 32],
+        /// The number of ephemeral headers stored.
+        ephemeral_header_count: u16,
+    },
+    /// The node could not process the PING.
+    Error {
+        /// The error code.
+        error_code: u16,
+        /// A utf8 description of the error.
+        message: Vec<u8>,
+    },
+}
+
+impl PingPayload {
+    /// The payload type of the payload.
+    pub fn payload_type(&self) -> u16 {
+        match self {
+            PingPayload::Capabilities { .. } => CAPABILITIES_PAYLOAD,
+            PingPayload::BasicRadius { .. } => BASIC_RADIUS_PAYLOAD,
+            PingPayload::HistoryRadius { .. } => HISTORY_RADIUS_PAYLOAD,
+            PingPayload::Error { .. } => ERROR_PAYLOAD,
+        }
+    }
+
+    /// SSZ encodes the payload.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            PingPayload::Capabilities {
+                client_info,
+                data_radius,
+                capabilities,
+            } => {
+                let fixed = OFFSET_LENGTH + 32 + OFFSET_LENGTH;
+                bytes.extend_from_slice(&offset(fixed));
+                bytes.extend_from_slice(data_radius);
+                bytes.extend_from_slice(&offset(fixed + client_info.len()));
+                bytes.extend_from_slice(client_info);
+                for capability in capabilities {
+                    bytes.extend_from_slice(&capability.to_le_bytes());
+                }
+            }
+            PingPayload::BasicRadius { data_radius } => bytes.extend_from_slice(data_radius),
+            PingPayload::HistoryRadius {
+                data_radius,
+                ephemeral_header_count,
+            } => {
+                bytes.extend_from_slice(data_radius);
+                bytes.extend_from_slice(&ephemeral_header_count.to_le_bytes());
+            }
+            PingPayload::Error {
+                error_code,
+                message,
+            } => {
+                bytes.extend_from_slice(&error_code.to_le_bytes());
+                bytes.extend_from_slice(&offset(2 + OFFSET_LENGTH));
+                bytes.extend_from_slice(message);
+            }
+        }
+        bytes
+    }
+
+    /// Decodes an SSZ encoded payload of the given type.
+    pub fn decode(payload_type: u16, bytes: &[u8]) -> eyre::Result<Self> {
+        match payload_type {
+            CAPABILITIES_PAYLOAD => {
+                let fixed = OFFSET_LENGTH + 32 + OFFSET_LENGTH;
+                if bytes.len() < fixed {
+                    eyre::bail!("Payload too short");
+                }
+                let client_info_offset = read_offset(bytes, 0)?;
+                let capabilities_offset = read_offset(bytes, OFFSET_LENGTH + 32)?;
+                if client_info_offset != fixed
+                    || capabilities_offset < client_info_offset
+                    || capabilities_offset > bytes.len()
+                {
+                    eyre::bail!("Invalid payload offsets");
+                }
+                let capabilities = &bytes[capabilities_offset..];
+                if !capabilities.len().is_multiple_of(2) {
+                    eyre::bail!("Invalid capabilities length");
+                }
+                Ok(PingPayload::Capabilities {
+                    client_info: bytes[client_info_offset..capabilities_offset].to_vec(),
+                    data_radius: bytes[OFFSET_LENGTH..OFFSET_LENGTH + 32]
+                        .try_into()
+                        .expect("32 bytes"),
+                    capabilities: capabilities
+                        .chunks_exact(2)
+                        .map(|capability| u16::from_le_bytes([capability[0], capability[1]]))
+                        .collect(),
+                })
+            }
+            BASIC_RADIUS_PAYLOAD => Ok(PingPayload::BasicRadius {
+                data_radius: bytes
+                    .try_into()
+                    .map_err(|_| eyre::eyre!("Invalid radius length"))?,
+            }),
+            HISTORY_RADIUS_PAYLOAD => {
+                if bytes.len() != 34 {
+                    eyre::bail!("Invalid payload length");
+                }
+                Ok(PingPayload::HistoryRadius {
+                    data_radius: bytes[..32].try_into().expect("32 bytes"),
+                    ephemeral_header_count: u16::from_le_bytes([bytes[32], bytes[33]]),
+                })
+            }
+            ERROR_PAYLOAD => {
+                if bytes.len() < 2 {
+                    eyre::bail!("Payload too short");
+                }
+                Ok(PingPayload::Error {
+                    error_code: u16::from_le_bytes([bytes[0], bytes[1]]),
+                    message: variable_field(bytes, 2, 2 + OFFSET_LENGTH)?.to_vec(),
+                })
+            }
+            payload_type => eyre::bail!("Unknown payload type {payload_type}"),
+        }
+    }
+}
+
+/// Formats a little endian uint256 data radius as big endian hex along with the fraction of the
+/// keyspace it covers.
+pub fn format_radius(data_radius: &[u8; 32]) -> String {
+    let big_endian: Vec<u8> = data_radius.iter().rev().cloned().collect();
+    let top = u64::from_be_bytes(big_endian[..8].try_into().expect("8 bytes"));
+    format!(
+        "0x{} ({:.2}% of the keyspace)",
+        hex::encode(&big_endian),
+        top as f64 / u64::MAX as f64 * 100.0
+    )
+}
+
+/// Encodes an SSZ offset.
+fn offset(offset: usize) -> [u8; OFFSET_LENGTH] {
+    (offset as u32).to_le_bytes()
+}
+
+/// Reads the SSZ offset at the position.
+fn read_offset(bytes: &[u8], position: usize) -> eyre::Result<usize> {
+    let offset = bytes
+        .get(position..position + OFFSET_LENGTH)
+        .ok_or_else(|| eyre::eyre!("Message too short"))?;
+    Ok(u32::from_le_bytes(offset.try_into().expect("4 bytes")) as usize)
+}
+
+/// Returns the only variable sized field of a container, whose offset is at `position` and whose
+/// fixed part is `fixed` bytes long.
+fn variable_field(bytes: &[u8], position: usize, fixed: usize) -> eyre::Result<&[u8]> {
+    if read_offset(bytes, position)? != fixed {
+        eyre::bail!("Invalid offset");
+    }
+    Ok(&bytes[fixed..])
+}
+
+/// SSZ encodes a list of RLP encoded ENRs.
+fn encode_enrs(enrs: &[Enr]) -> Vec<u8> {
+    let encoded: Vec<Vec<u8>> = enrs.iter().map(alloy_rlp::encode).collect();
+    let mut bytes = Vec::new();
+    let mut position = encoded.len() * OFFSET_LENGTH;
+    for enr in &encoded {
+        bytes.extend_from_slice(&offset(position));
+        position += enr.len();
+    }
+    for enr in &encoded {
+        bytes.extend_from_slice(enr);
+    }
+    bytes
+}
+
+/// Decodes an SSZ list of RLP encoded ENRs.
+fn decode_enrs(bytes: &[u8]) -> eyre::Result<Vec<Enr>> {
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let first = read_offset(bytes, 0)?;
+    if !first.is_multiple_of(OFFSET_LENGTH) || first == 0 || first > bytes.len() {
+        eyre::bail!("Invalid ENR list offset");
+    }
+    let mut offsets = (0..first / OFFSET_LENGTH)
+        .map(|index| read_offset(bytes, index * OFFSET_LENGTH))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    offsets.push(bytes.len());
+    offsets
+        .windows(2)
+        .map(|window| {
+            let (start, end) = (window[0], window[1]);
+            if start > end || end > bytes.len() {
+                eyre::bail!("Invalid ENR list offset");
+            }
+            let mut enr = &bytes[start..end];
+            let decoded = Enr::decode(&mut enr).map_err(|e| eyre::eyre!("Invalid ENR: {e}"))?;
+            Ok(decoded)
+        })
+        .collect()
+}
@@ -0,0 +1,130 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    time::Instant,
+};
+
+use discv5::Discv5;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+
+use super::{
+    failure::{Failure, RetryPolicy},
+    target::Target,
+};
+
+/// The outcome of requesting the ENR of a single target.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct TargetReport {
+    /// The target as given in the input.
+    pub target: String,
+    /// Whether an ENR was obtained.
+    pub success: bool,
+    /// The time taken for the request to complete or fail, including retries.
+    pub latency_ms: u128,
+    /// The number of requests sent.
+    pub attempts: u32,
+    /// The obtained ENR.
+    pub enr: Option<String>,
+    /// The node id of the obtained ENR.
+    pub node_id: Option<String>,
+    /// The sequence number of the obtained ENR.
+    pub seq: Option<u64>,
+    /// The class of the failure, see [`Failure::kind`].
+    pub failure: Option<&'static str>,
+    /// The reason the request failed.
+    pub error: Option<String>,
+}
+
+/// Reads one target per line from a file, or stdin if the file is `-`. Empty lines and lines
+/// starting with `#` are skipped.
+pub fn read_targets(file: &str) -> eyre::Result<Vec<(String, Result<Target, String>)>> {
+    let reader: Box<dyn BufRead> = if file == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(file)?))
+    };
+
+    let mut targets = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let entry = line.trim();
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        targets.push((entry.to_string(), entry.parse::<Target>()));
+    }
+    Ok(targets)
+}
+
+/// Requests the ENRs of all targets with at most `parallelism` requests in flight, retrying
+/// failed requests according to the policy. The reports are in the order of the targets.
+pub async fn request_all(
+    discv5: &Discv5,
+    targets: Vec<(String, Result<Target, String>)>,
+    parallelism: usize,
+    policy: RetryPolicy,
+) -> Vec<TargetReport> {
+    let mut reports: Vec<(usize, TargetReport)> = stream::iter(targets.into_iter().enumerate())
+        .map(|(index, (input, target))| async move {
+            let start = Instant::now();
+            let (result, attempts) = match target {
+                Ok(target) => target.request_with_retries(discv5, policy).await,
+                Err(e) => (Err(Failure::InvalidTarget(e)), 0),
+            };
+            let latency_ms = start.elapsed().as_millis();
+            match result {
+                Ok(enr) => {
+                    log::info!("{input}: ENR obtained in {latency_ms}ms");
+                    let report = TargetReport {
+                        target: input,
+                        success: true,
+                        latency_ms,
+                        attempts,
+                        enr: Some(enr.to_base64()),
+                        node_id: Some(hex::encode(enr.node_id().raw())),
+                        seq: Some(enr.seq()),
+                        failure: None,
+                        error: None,
+                    };
+                    (index, report)
+                }
+                Err(failure) => {
+                    log::warn!(
+                        "{input}: Failed after {attempts} attempts and {latency_ms}ms. Error: {failure}"
+                    );
+                    let report = TargetReport {
+                        target: input,
+                        success: false,
+                        latency_ms,
+                        attempts,
+                        enr: None,
+                        node_id: None,
+                        seq: None,
+                        failure: Some(failure.kind()),
+                        error: Some(failure.to_string()),
+                    };
+                    (index, report)
+                }
+            }
+        })
+        .buffer_unordered(parallelism.max(1))
+        .collect()
+        .await;
+    // Requests complete in any order
+    reports.sort_by_key(|(index, _)| *index);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
+
+/// Writes the reports as JSON lines to the file, or stdout if no file is given.
+pub fn write_report(reports: &[TargetReport], file: Option<&str>) -> eyre::Result<()> {
+    let mut writer: Box<dyn Write> = match file {
+        Some(file) => Box::new(File::create(file)?),
+        None => Box::new(std::io::stdout()),
+    };
+    for report in reports {
+        serde_json::to_writer(&mut writer, report)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
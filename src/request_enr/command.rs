@@ -1,29 +1,84 @@
-use clap::Args;
+use clap::{Args, ValueEnum};
+
+use crate::utils::local_node::LocalNode;
+
+/// The format a single obtained ENR is printed in.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum OutputFormat {
+    /// Log lines describing the ENR
+    #[default]
+    Text,
+    /// A single JSON object on stdout
+    Json,
+}
 
 /// RequestEnr
 #[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct RequestEnr {
-    /// Multiaddr of the node to request the ENR from.
+    /// The node to request the ENR from.
     #[clap(
         short = 'm',
-        long = "multiaddr",
-        help = "The multiaddr of the node to request their ENR from"
+        long = "target",
+        alias = "multiaddr",
+        allow_hyphen_values = true,
+        required_unless_present = "targets_file",
+        conflicts_with = "targets_file",
+        help = "The node to request their ENR from, as a multiaddr, ENR, enode URL or <public-key>@<ip>:<port>"
+    )]
+    pub target: Option<String>,
+    /// A file of targets to request ENRs from concurrently.
+    #[clap(
+        short = 'f',
+        long = "targets-file",
+        help = "A file with one target (multiaddr, ENR, enode URL or <public-key>@<ip>:<port>) per line to request ENRs from concurrently. Use - to read from stdin."
+    )]
+    pub targets_file: Option<String>,
+    /// The maximum number of concurrent requests in batch mode.
+    #[clap(
+        short = 'j',
+        long = "parallelism",
+        help = "The maximum number of concurrent requests when using --targets-file.",
+        default_value = "16"
+    )]
+    pub parallelism: usize,
+    /// The file to write the batch report to.
+    #[clap(
+        short = 'r',
+        long = "report",
+        help = "The file to write the JSON lines report of a --targets-file run to. The report is printed to stdout if not specified."
+    )]
+    pub report: Option<String>,
+    /// How long to wait for a response to each attempt.
+    #[clap(
+        short = 't',
+        long = "timeout",
+        help = "The number of milliseconds to wait for a response to each request attempt.",
+        default_value = "1000"
+    )]
+    pub timeout: u64,
+    /// The number of times a failed request is retried.
+    #[clap(
+        long = "retries",
+        help = "The number of times a request is retried if the node does not respond or the handshake fails.",
+        default_value = "1"
     )]
-    pub multiaddr: String,
-    /// Specifies the listening address of the server.
+    pub retries: u32,
+    /// The delay before the first retry.
     #[clap(
-        short = 'l',
-        long = "listen-address",
-        help = "Specifies the IPv4 listening address of the server.",
-        default_value = "0.0.0.0"
+        long = "backoff",
+        help = "The number of milliseconds to wait before the first retry. The delay doubles with every further retry.",
+        default_value = "500"
     )]
-    pub listen_address: String,
-    /// Specifies the listening UDP port of the server.
+    pub backoff: u64,
+    /// The format the obtained ENR is printed in.
     #[clap(
-        short = 'p',
-        long = "listen-port",
-        help = "Specifies the listening UDP port of the server.",
-        default_value = "9001"
+        short = 'o',
+        long = "output",
+        help = "The format the obtained ENR is printed in. JSON is printed to stdout, --targets-file runs always write a JSON lines report.",
+        default_value = "text"
     )]
-    pub listen_port: u16,
+    pub output: OutputFormat,
+    /// The local node the requests are sent from.
+    #[clap(flatten)]
+    pub local_node: LocalNode,
 }
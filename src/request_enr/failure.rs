@@ -0,0 +1,114 @@
+use std::{fmt, time::Duration};
+
+use discv5::{enr::NodeId, RequestError};
+
+/// The reason an ENR could not be obtained from a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// The target could not be parsed or contacted.
+    InvalidTarget(String),
+    /// The target only has sockets of address families the local node does not listen on.
+    Unreachable(String),
+    /// The node did not respond before the request timed out.
+    ///
+    /// A node that cannot decrypt our handshake, for example because the public key of the target
+    /// is wrong, silently drops it, so this is also reported if the target's peer id is incorrect.
+    NoResponse,
+    /// The node responded, but a session could not be established.
+    HandshakeFailed(String),
+    /// The node did not return a valid ENR.
+    InvalidEnr(String),
+    /// The node returned an ENR of a different node than the one targeted.
+    NodeIdMismatch {
+        /// The node id derived from the target.
+        expected: NodeId,
+        /// The node id of the returned ENR.
+        received: NodeId,
+    },
+    /// Any other failure of the local node.
+    Other(String),
+}
+
+impl Failure {
+    /// A short, stable name of the failure class for reports and scripts.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Failure::InvalidTarget(_) => "invalid-target",
+            Failure::Unreachable(_) => "unreachable",
+            Failure::NoResponse => "no-response",
+            Failure::HandshakeFailed(_) => "handshake-failed",
+            Failure::InvalidEnr(_) => "invalid-enr",
+            Failure::NodeIdMismatch { .. } => "node-id-mismatch",
+            Failure::Other(_) => "other",
+        }
+    }
+
+    /// Whether another attempt could succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Failure::NoResponse | Failure::HandshakeFailed(_))
+    }
+}
+
+impl From<RequestError> for Failure {
+    fn from(error: RequestError) -> Self {
+        match error {
+            RequestError::Timeout => Failure::NoResponse,
+            RequestError::InvalidRemotePacket => {
+                Failure::HandshakeFailed("The node sent an invalid packet".into())
+            }
+            RequestError::EncryptionFailed(e) => Failure::HandshakeFailed(e),
+            RequestError::InvalidRemoteEnr => {
+                Failure::InvalidEnr("The node sent an invalid ENR during the handshake".into())
+            }
+            RequestError::InvalidEnr(e) => Failure::InvalidEnr(e.into()),
+            RequestError::InvalidMultiaddr(e) => Failure::InvalidTarget(e.into()),
+            error => Failure::Other(error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::InvalidTarget(e) => write!(f, "Invalid target: {e}"),
+            Failure::Unreachable(e) => write!(f, "Unreachable: {e}"),
+            Failure::NoResponse => write!(
+                f,
+                "No response. The node is unreachable or could not decrypt our handshake"
+            ),
+            Failure::HandshakeFailed(e) => write!(f, "Handshake failed: {e}"),
+            Failure::InvalidEnr(e) => write!(f, "Invalid ENR: {e}"),
+            Failure::NodeIdMismatch { expected, received } => write!(
+                f,
+                "Node id mismatch: expected {expected}, the node returned the ENR of {received}"
+            ),
+            Failure::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// How often and how quickly failed requests are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of attempts after the first one.
+    pub retries: u32,
+    /// The delay before the first retry, which doubles with every further retry.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// The delay before the given retry, starting at 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 0,
+            backoff: Duration::ZERO,
+        }
+    }
+}
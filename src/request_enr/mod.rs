@@ -1,51 +1,122 @@
-use discv5::{enr, ListenConfig};
-use libp2p_core::Multiaddr;
+use std::time::Duration;
 
-mod enr_ext;
-use enr_ext::EnrExt;
-use std::net::Ipv4Addr;
+use discv5::{enr, ConfigBuilder};
+
+use crate::utils::{enr_ext::EnrExt, eth2::EnrForkId};
 
 /// The [clap] cli command arguments for the request-enr service.
 pub mod command;
 pub use command::*;
 
-/// Runs the request ENR command.
-pub async fn run(req: &RequestEnr) {
-    // Obtain the multiaddr
-    let multiaddr = req
-        .multiaddr
-        .parse::<Multiaddr>()
-        .expect("Invalid Multiaddr provided");
+/// Concurrent ENR requests to a list of targets.
+pub mod batch;
+
+/// The classification of failed requests and their retries.
+pub mod failure;
+use failure::RetryPolicy;
 
-    // Set up a server to receive the response
-    let listen_address = req
-        .listen_address
-        .parse::<Ipv4Addr>()
-        .expect("Invalid listening address");
+/// The machine readable description of obtained ENRs.
+pub mod output;
+use output::EnrDetails;
 
-    let listen_port = req.listen_port;
-    let listen_config = ListenConfig::from_ip(listen_address.into(), listen_port);
-    let enr_key = enr::CombinedKey::generate_secp256k1();
+/// The nodes ENRs can be requested from.
+pub mod target;
+use target::Target;
 
-    // Build a local ENR
-    let enr = enr::Builder::default()
-        .ip4(listen_address)
-        .udp4(listen_port)
-        .build(&enr_key)
-        .unwrap();
+/// Runs the request ENR command. Returns whether all requested ENRs were obtained.
+pub async fn run(req: &RequestEnr) -> bool {
+    let policy = RetryPolicy {
+        retries: req.retries,
+        backoff: Duration::from_millis(req.backoff),
+    };
+    // Retries are handled per request instead of by the session handler, so failures can be
+    // classified and backed off.
+    let configure = |config: &mut ConfigBuilder| {
+        config
+            .request_timeout(Duration::from_millis(req.timeout))
+            .request_retries(0);
+    };
 
-    // Construct the discv5 service
-    let config = discv5::ConfigBuilder::new(listen_config).build();
-    let mut discv5: discv5::Discv5 = discv5::Discv5::new(enr, enr_key, config).unwrap();
+    if let Some(targets_file) = &req.targets_file {
+        let targets = match batch::read_targets(targets_file) {
+            Ok(targets) => targets,
+            Err(e) => {
+                log::error!("Failed to read the targets file. Error: {}", e);
+                return false;
+            }
+        };
+        let ipv6 = targets
+            .iter()
+            .any(|(_, target)| matches!(target, Ok(target) if target.is_ipv6()));
+        let discv5 = match req.local_node.start(ipv6, configure).await {
+            Ok(discv5) => discv5,
+            Err(e) => {
+                log::error!("Failed to start the local node. Error: {}", e);
+                return false;
+            }
+        };
 
-    // Start the server
-    discv5.start().await.unwrap();
+        log::info!(
+            "Requesting ENRs for {} targets, {} at a time",
+            targets.len(),
+            req.parallelism
+        );
+        let reports = batch::request_all(&discv5, targets, req.parallelism, policy).await;
+        let successes = reports.iter().filter(|report| report.success).count();
+        log::info!("Obtained {} of {} ENRs", successes, reports.len());
+        if let Err(e) = batch::write_report(&reports, req.report.as_deref()) {
+            log::error!("Failed to write the report. Error: {}", e);
+        }
+        return successes == reports.len();
+    }
+
+    // Obtain the target
+    let target = match req.target.as_deref().unwrap_or_default().parse::<Target>() {
+        Ok(target) => target,
+        Err(e) => {
+            log::error!("Invalid target provided. Error: {}", e);
+            return false;
+        }
+    };
+    let discv5 = match req.local_node.start(target.is_ipv6(), configure).await {
+        Ok(discv5) => discv5,
+        Err(e) => {
+            log::error!("Failed to start the local node. Error: {}", e);
+            return false;
+        }
+    };
 
     // Request the ENR
-    log::info!("Requesting ENR for: {}", multiaddr);
-    match discv5.request_enr(multiaddr.to_string()).await {
-        Ok(enr) => print_enr(enr),
-        Err(e) => log::error!("Failed to obtain ENR. Error: {}", e),
+    log::info!("Requesting ENR for: {}", target);
+    if let (Some(node_id), Some(socket)) = (target.node_id(), target.socket()) {
+        log::info!("Contact node id: {}, socket: {}", node_id, socket);
+    }
+    match target.request_with_retries(&discv5, policy).await {
+        (Ok(enr), _) => match req.output {
+            OutputFormat::Text => {
+                print_enr(enr);
+                true
+            }
+            OutputFormat::Json => match serde_json::to_string(&EnrDetails::from(&enr)) {
+                Ok(json) => {
+                    println!("{json}");
+                    true
+                }
+                Err(e) => {
+                    log::error!("Failed to encode the ENR as JSON. Error: {}", e);
+                    false
+                }
+            },
+        },
+        (Err(failure), attempts) => {
+            log::error!(
+                "Failed to obtain ENR after {} attempts ({}). Error: {}",
+                attempts,
+                failure.kind(),
+                failure
+            );
+            false
+        }
     }
 }
 
@@ -64,6 +135,29 @@ fn print_enr(enr: enr::Enr<enr::CombinedKey>) {
     if let Some(udp) = enr.udp4() {
         log::info!("UDP Port:{}", udp);
     }
+    if let Some(ip6) = enr.ip6() {
+        log::info!("IPv6:{:?}", ip6);
+    }
+    if let Some(tcp6) = enr.tcp6() {
+        log::info!("TCP6 Port:{}", tcp6);
+    }
+    if let Some(udp6) = enr.udp6() {
+        log::info!("UDP6 Port:{}", udp6);
+    }
+    match EnrForkId::from_enr(&enr) {
+        Some(Ok(fork_id)) => {
+            log::info!("Fork Digest:0x{}", hex::encode(fork_id.fork_digest));
+            if fork_id.has_next_fork() {
+                log::info!(
+                    "Next Fork:0x{} at epoch {}",
+                    hex::encode(fork_id.next_fork_version),
+                    fork_id.next_fork_epoch
+                );
+            }
+        }
+        Some(Err(e)) => log::warn!("{}", e),
+        None => {}
+    }
 
     let multiaddrs = enr.multiaddr();
     if !multiaddrs.is_empty() {
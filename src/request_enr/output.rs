@@ -0,0 +1,98 @@
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use discv5::enr;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{enr_ext::EnrExt, eth2::EnrForkId};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The machine readable description of an ENR.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnrDetails {
+    /// The base64 encoded ENR.
+    pub enr: String,
+    /// The sequence number of the ENR.
+    pub seq: u64,
+    /// The hex encoded node id.
+    pub node_id: String,
+    /// The libp2p peer id of the node's key.
+    pub peer_id: String,
+    /// The ipv4 address.
+    pub ip4: Option<Ipv4Addr>,
+    /// The ipv4 TCP port.
+    pub tcp4: Option<u16>,
+    /// The ipv4 UDP port.
+    pub udp4: Option<u16>,
+    /// The ipv6 address.
+    pub ip6: Option<Ipv6Addr>,
+    /// The ipv6 TCP port.
+    pub tcp6: Option<u16>,
+    /// The ipv6 UDP port.
+    pub udp6: Option<u16>,
+    /// Every key of the ENR with its hex encoded RLP value.
+    pub keys: BTreeMap<String, String>,
+    /// The decoded `eth2` field, if present.
+    pub eth2: Option<Eth2Details>,
+    /// The multiaddrs of the node.
+    pub multiaddrs: Vec<String>,
+}
+
+/// The decoded `eth2` field of an ENR.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Eth2Details {
+    /// The fork id of the node.
+    ForkId {
+        /// The hex encoded fork digest.
+        fork_digest: String,
+        /// The hex encoded version of the next fork.
+        next_fork_version: String,
+        /// The epoch of the next fork.
+        next_fork_epoch: u64,
+    },
+    /// The field could not be decoded.
+    Invalid {
+        /// The reason the field could not be decoded.
+        error: String,
+    },
+}
+
+impl From<&Enr> for EnrDetails {
+    fn from(enr: &Enr) -> Self {
+        let eth2 = EnrForkId::from_enr(enr).map(|fork_id| match fork_id {
+            Ok(fork_id) => Eth2Details::ForkId {
+                fork_digest: format!("0x{}", hex::encode(fork_id.fork_digest)),
+                next_fork_version: format!("0x{}", hex::encode(fork_id.next_fork_version)),
+                next_fork_epoch: fork_id.next_fork_epoch,
+            },
+            Err(error) => Eth2Details::Invalid { error },
+        });
+        EnrDetails {
+            enr: enr.to_base64(),
+            seq: enr.seq(),
+            node_id: hex::encode(enr.node_id().raw()),
+            peer_id: enr.peer_id().to_string(),
+            ip4: enr.ip4(),
+            tcp4: enr.tcp4(),
+            udp4: enr.udp4(),
+            ip6: enr.ip6(),
+            tcp6: enr.tcp6(),
+            udp6: enr.udp6(),
+            keys: enr
+                .iter()
+                .map(|(key, value)| {
+                    (
+                        String::from_utf8_lossy(key).into_owned(),
+                        format!("0x{}", hex::encode(value)),
+                    )
+                })
+                .collect(),
+            eth2,
+            multiaddrs: enr.multiaddr().iter().map(ToString::to_string).collect(),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use discv5::{
+    enr::{self, NodeId},
+    handler::NodeContact,
+    multiaddr::Protocol,
+    Discv5, IpMode, RequestError,
+};
+use libp2p_core::Multiaddr;
+use libp2p_identity::PeerId;
+
+use super::failure::{Failure, RetryPolicy};
+use crate::utils::{
+    enr_ext::{CombinedKeyPublicExt, PeerIdExt},
+    node_id,
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// A node to request an ENR from.
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// A libp2p multiaddr with a `/udp` port and a `/p2p` peer id. Enode URLs and
+    /// `<public-key>@<ip>:<port>` contacts are converted into this form.
+    Multiaddr(Multiaddr),
+    /// A possibly outdated ENR of the node.
+    Enr(Enr),
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    /// Parses a multiaddr, a base64 ENR, an enode URL or a `<public-key>@<ip>:<port>` contact.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with("enr:") {
+            return Enr::from_str(s).map(Target::Enr);
+        }
+        if let Some(enode) = s.strip_prefix("enode://") {
+            return parse_contact(enode).map(Target::Multiaddr);
+        }
+        if s.contains('@') {
+            return parse_contact(s).map(Target::Multiaddr);
+        }
+        s.parse::<Multiaddr>()
+            .map(Target::Multiaddr)
+            .map_err(|e| format!("Invalid target {s}: {e}"))
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Multiaddr(multiaddr) => write!(f, "{multiaddr}"),
+            Target::Enr(enr) => write!(f, "{}", enr.to_base64()),
+        }
+    }
+}
+
+impl Target {
+    /// Whether the target can only be reached over ipv6.
+    pub fn is_ipv6(&self) -> bool {
+        match self {
+            Target::Multiaddr(multiaddr) => multiaddr
+                .iter()
+                .any(|protocol| matches!(protocol, Protocol::Ip6(_))),
+            Target::Enr(enr) => enr.udp4_socket().is_none() && enr.udp6_socket().is_some(),
+        }
+    }
+
+    /// The node id of the target, if it can be derived.
+    pub fn node_id(&self) -> Option<NodeId> {
+        match self {
+            Target::Multiaddr(multiaddr) => multiaddr.iter().find_map(|protocol| match protocol {
+                Protocol::P2p(peer_id) => peer_id.to_combined_public_key().ok().map(NodeId::from),
+                _ => None,
+            }),
+            Target::Enr(enr) => Some(enr.node_id()),
+        }
+    }
+
+    /// The UDP socket the request is sent to, if it can be derived.
+    pub fn socket(&self) -> Option<SocketAddr> {
+        match self {
+            Target::Multiaddr(multiaddr) => {
+                let mut ip = None;
+                let mut port = None;
+                for protocol in multiaddr.iter() {
+                    match protocol {
+                        Protocol::Ip4(ip4) => ip = Some(IpAddr::from(ip4)),
+                        Protocol::Ip6(ip6) => ip = Some(IpAddr::from(ip6)),
+                        Protocol::Udp(udp) => port = Some(udp),
+                        _ => {}
+                    }
+                }
+                Some(SocketAddr::new(ip?, port?))
+            }
+            Target::Enr(enr) => enr
+                .udp4_socket()
+                .map(SocketAddr::from)
+                .or_else(|| enr.udp6_socket().map(SocketAddr::from)),
+        }
+    }
+
+    /// The contact used to send requests to the target from a node listening in `ip_mode`.
+    pub fn contact(&self, ip_mode: IpMode) -> Result<NodeContact, String> {
+        match self {
+            Target::Multiaddr(multiaddr) => {
+                NodeContact::try_from_multiaddr(multiaddr.clone()).map_err(String::from)
+            }
+            Target::Enr(enr) => NodeContact::try_from_enr(enr.clone(), ip_mode)
+                .map_err(|_| format!("The ENR has no socket reachable over {ip_mode:?}")),
+        }
+    }
+
+    /// Returns the ENR of the target, requesting it if only a multiaddr is known.
+    pub async fn resolve(&self, discv5: &Discv5) -> Result<Enr, RequestError> {
+        match self {
+            Target::Multiaddr(_) => self.request(discv5).await,
+            Target::Enr(enr) => Ok(enr.clone()),
+        }
+    }
+
+    /// Requests the latest ENR of the target.
+    pub async fn request(&self, discv5: &Discv5) -> Result<Enr, RequestError> {
+        match self {
+            Target::Multiaddr(multiaddr) => discv5.request_enr(multiaddr.to_string()).await,
+            Target::Enr(enr) => discv5
+                .find_node_designated_peer(enr.clone(), vec![0])
+                .await?
+                .pop()
+                .ok_or(RequestError::InvalidEnr("Peer did not return an ENR")),
+        }
+    }
+
+    /// Requests the latest ENR of the target and checks that it belongs to the targeted node.
+    pub async fn request_verified(&self, discv5: &Discv5) -> Result<Enr, Failure> {
+        let enr = self.request(discv5).await?;
+        match self.node_id() {
+            Some(expected) if expected != enr.node_id() => Err(Failure::NodeIdMismatch {
+                expected,
+                received: enr.node_id(),
+            }),
+            _ => Ok(enr),
+        }
+    }
+
+    /// Requests the latest ENR of the target, retrying failures that may be transient according
+    /// to the policy. Returns the result of the last attempt and the number of attempts made.
+    pub async fn request_with_retries(
+        &self,
+        discv5: &Discv5,
+        policy: RetryPolicy,
+    ) -> (Result<Enr, Failure>, u32) {
+        let mut attempts = 1;
+        loop {
+            let result = self.request_verified(discv5).await;
+            match result {
+                Err(ref failure) if failure.is_retryable() && attempts <= policy.retries => {
+                    let delay = policy.delay(attempts);
+                    log::debug!(
+                        "{self}: Attempt {attempts} failed ({}), retrying in {}ms",
+                        failure.kind(),
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempts += 1;
+                }
+                result => return (result, attempts),
+            }
+        }
+    }
+}
+
+/// Converts an `<public-key>@<ip>:<port>[?discport=<port>]` contact, as found in enode URLs, into
+/// a multiaddr.
+///
+/// A node id is not sufficient as the discv5 handshake requires the node's public key.
+fn parse_contact(contact: &str) -> Result<Multiaddr, String> {
+    let (id, address) = contact
+        .split_once('@')
+        .ok_or_else(|| format!("Invalid contact {contact}, expected <public-key>@<ip>:<port>"))?;
+    let (address, query) = match address.split_once('?') {
+        Some((address, query)) => (address, Some(query)),
+        None => (address, None),
+    };
+    let mut socket = address
+        .parse::<SocketAddr>()
+        .map_err(|_| format!("Invalid socket address {address}"))?;
+    // The UDP discovery port differs from the TCP port if `discport` is set.
+    if let Some(discport) = query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .find_map(|param| param.strip_prefix("discport="))
+    {
+        socket.set_port(
+            discport
+                .parse()
+                .map_err(|_| format!("Invalid discport {discport}"))?,
+        );
+    }
+
+    let raw =
+        hex::decode(id.trim_start_matches("0x")).map_err(|_| format!("Invalid hex id {id}"))?;
+    if raw.len() == 32 {
+        return Err(format!(
+            "{id} is a node id, the discv5 handshake requires the node's public key instead"
+        ));
+    }
+    let public_key = node_id::secp256k1_public_key(&raw).map_err(|e| e.to_string())?;
+    let peer_id: PeerId = public_key.into_peer_id();
+
+    let mut multiaddr = Multiaddr::from(socket.ip());
+    multiaddr.push(Protocol::Udp(socket.port()));
+    multiaddr.push(Protocol::P2p(peer_id));
+    Ok(multiaddr)
+}
@@ -1,8 +1,11 @@
 use std::{fs::File, io::BufReader, str::FromStr};
 
-use discv5::{Discv5, Enr};
+use discv5::{ConnectionDirection, ConnectionState, Discv5, Enr};
+use libp2p_core::{multiaddr::Protocol, Multiaddr};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::enr_ext::EnrExt;
+
 /// The top level bootstrap object.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct BootstrapStore {
@@ -47,16 +50,25 @@ pub enum State {
     Disconnected,
 }
 
-/// Function to bootstrap peers using a JSON file.
-pub async fn boostrap(discv5: &mut Discv5, file: Option<String>) -> eyre::Result<()> {
-    if let Some(f) = file {
-        // Read the JSON bootstrap file
-        let file = File::open(f)?;
-        let reader = BufReader::new(file);
-        let bootstrap_store: BootstrapStore = serde_json::from_reader(reader)?;
+impl BootstrapStore {
+    /// A snapshot of the routing table, listing connected nodes first.
+    pub fn from_table(discv5: &Discv5) -> Self {
+        let mut data: Vec<BootstrapNode> = discv5
+            .table_entries()
+            .into_iter()
+            .map(|(_, enr, status)| {
+                BootstrapNode::new(&enr, status.state.into(), status.direction.into())
+            })
+            .collect();
+        data.sort_by_key(|node| node.state != State::Connected);
+        BootstrapStore { data }
+    }
 
-        // For each bootstrap node, try to connect to it.
-        for node in bootstrap_store.data {
+    /// Adds the nodes to the routing table, skipping invalid ENRs. Returns the number of nodes
+    /// added.
+    pub fn add_to_table(&self, discv5: &Discv5) -> usize {
+        let mut added = 0;
+        for node in &self.data {
             // Skip over invalid enrs
             if let Ok(enr) = Enr::from_str(&node.enr) {
                 let node_id = enr.node_id();
@@ -64,10 +76,66 @@ pub async fn boostrap(discv5: &mut Discv5, file: Option<String>) -> eyre::Result
                     Err(_) => { /* log::warn!("Failed to bootstrap node with id: {node_id}") */ }
                     Ok(_) => {
                         log::debug!("Bootstrapped node: {node_id}");
+                        added += 1;
                     }
                 }
             }
         }
+        added
+    }
+}
+
+impl BootstrapNode {
+    /// The bootstrap node of an ENR, addressed by its UDP socket.
+    pub fn new(enr: &Enr, state: State, direction: Direction) -> Self {
+        let last_seen_p2p_address = enr
+            .udp4_socket()
+            .map(std::net::SocketAddr::from)
+            .or_else(|| enr.udp6_socket().map(std::net::SocketAddr::from))
+            .map(|socket| {
+                let mut multiaddr = Multiaddr::from(socket.ip());
+                multiaddr.push(Protocol::Udp(socket.port()));
+                multiaddr.to_string()
+            })
+            .unwrap_or_default();
+        BootstrapNode {
+            peer_id: enr.peer_id().to_string(),
+            enr: enr.to_base64(),
+            last_seen_p2p_address,
+            state,
+            direction,
+        }
+    }
+}
+
+impl From<ConnectionState> for State {
+    fn from(state: ConnectionState) -> Self {
+        match state {
+            ConnectionState::Connected => State::Connected,
+            ConnectionState::Disconnected => State::Disconnected,
+        }
+    }
+}
+
+impl From<ConnectionDirection> for Direction {
+    fn from(direction: ConnectionDirection) -> Self {
+        match direction {
+            ConnectionDirection::Incoming => Direction::Inbound,
+            ConnectionDirection::Outgoing => Direction::Outbound,
+        }
+    }
+}
+
+/// Function to bootstrap peers using a JSON file.
+pub async fn boostrap(discv5: &mut Discv5, file: Option<String>) -> eyre::Result<()> {
+    if let Some(f) = file {
+        // Read the JSON bootstrap file
+        let file = File::open(f)?;
+        let reader = BufReader::new(file);
+        let bootstrap_store: BootstrapStore = serde_json::from_reader(reader)?;
+
+        // For each bootstrap node, try to connect to it.
+        bootstrap_store.add_to_table(discv5);
     }
 
     Ok(())
@@ -1,4 +1,11 @@
-use clap::{Args, Subcommand as ClapSubcommand};
+use std::net::SocketAddr;
+
+use clap::{
+    ArgAction, ArgGroup, Args, Command, FromArgMatches, Subcommand as ClapSubcommand, ValueEnum,
+};
+use serde::Serialize;
+
+use super::{config::ConfigFormat, services::export::ExportFormat};
 
 /// Server Subcommand
 #[derive(ClapSubcommand, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -8,14 +15,129 @@ pub enum ServerSubcommand {
     Query,
     /// Prints the event stream.
     Events,
+    /// Sends FINDNODE requests to every reachable node and writes the discovered nodes.
+    Crawl(Crawl),
+    /// Searches for nodes advertising attestation or sync committee subnets.
+    SubnetSearch(SubnetSearch),
+    /// Prints the effective configuration, including any configuration file, and exits.
+    PrintConfig(PrintConfig),
+}
+
+/// Crawl Options
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Crawl {
+    /// The file to write the discovered nodes to.
+    #[clap(
+        short = 'o',
+        long = "output",
+        help = "The file to write the discovered nodes to. Defaults to stdout."
+    )]
+    pub output: Option<String>,
+    /// The number of nodes crawled concurrently.
+    #[clap(
+        short = 'p',
+        long = "parallelism",
+        help = "The number of nodes crawled concurrently.",
+        default_value = "16"
+    )]
+    pub parallelism: usize,
+    /// The number of distances requested per FINDNODE request.
+    #[clap(
+        long = "distances-per-request",
+        help = "The number of distances requested per FINDNODE request. Nodes limit the number of nodes they return per request across all requested distances, 16 in most implementations, so fewer distances per request find more of the nodes of full buckets at the cost of more requests. Responses split over several NODES packets are collected in full.",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..=16)
+    )]
+    pub distances_per_request: u64,
+    /// The number of consecutive distances without nodes after which the closer buckets of a node
+    /// are assumed to be empty, or 0 to request every distance.
+    #[clap(
+        long = "empty-distances",
+        help = "The number of consecutive distances without nodes after which the closer buckets of a node are assumed to be empty. Buckets are requested from distance 256 downwards. 0 requests every distance.",
+        default_value = "0"
+    )]
+    pub empty_distances: u64,
+}
+
+/// Subnet Search Options
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[clap(group(
+    ArgGroup::new("subnets")
+        .required(true)
+        .multiple(true)
+        .args(["attnets", "syncnets"])
+))]
+pub struct SubnetSearch {
+    /// The attestation subnets to search for.
+    #[clap(
+        short = 'a',
+        long = "attnet",
+        help = "An attestation subnet to search for, from 0 to 63. Can be given multiple times.",
+        value_parser = clap::value_parser!(u64).range(0..64)
+    )]
+    pub attnets: Vec<u64>,
+    /// The sync committee subnets to search for.
+    #[clap(
+        short = 's',
+        long = "syncnet",
+        help = "A sync committee subnet to search for, from 0 to 3. Can be given multiple times.",
+        value_parser = clap::value_parser!(u64).range(0..4)
+    )]
+    pub syncnets: Vec<u64>,
+    /// The number of matching nodes after which the search stops.
+    #[clap(
+        short = 'n',
+        long = "matches",
+        help = "The number of nodes advertising any of the subnets after which the search stops.",
+        default_value = "16"
+    )]
+    pub matches: usize,
+    /// The number of lookups after which the search stops.
+    #[clap(
+        long = "max-queries",
+        help = "The number of lookups after which the search stops, even without enough matches. 0 searches until enough nodes are found.",
+        default_value = "100"
+    )]
+    pub max_queries: u64,
+    /// The file to write the matching nodes to.
+    #[clap(
+        short = 'o',
+        long = "output",
+        help = "The file to write the matching nodes to as JSON. Defaults to stdout."
+    )]
+    pub output: Option<String>,
+}
+
+/// Print Config Options
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct PrintConfig {
+    /// The format to print the configuration in.
+    #[clap(
+        long = "format",
+        help = "The format to print the configuration in.",
+        default_value = "toml"
+    )]
+    pub format: ConfigFormat,
 }
 
 /// Server Command
-#[derive(Args, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Serializes into the configuration file format read by `--config`.
+#[derive(Args, Serialize, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
 pub struct Server {
     /// The service to run once the server is started.
     #[clap(subcommand)]
+    #[serde(skip)]
     pub service: ServerSubcommand,
+    /// A configuration file providing defaults for the other options.
+    #[clap(
+        short = 'c',
+        long = "config",
+        help = "A TOML or YAML (.yaml or .yml) file setting any of the other server options by their long name. Options given on the command line take precedence."
+    )]
+    #[serde(skip)]
+    pub config: Option<String>,
     /// Specifies the listening address of the server.
     #[clap(
         short = 'l',
@@ -34,7 +156,6 @@ pub struct Server {
     pub listen_port: u16,
     /// Optionally specify the listening ipv6 port.
     #[clap(
-        short = 'p',
         long = "listen-port-v6",
         help = "Specifies the listening UDP port of the server if an ipv6 address is specified as a listening address."
     )]
@@ -55,7 +176,6 @@ pub struct Server {
     pub enr_v4_port: Option<u16>,
     /// The port associated with an ipv6 address.
     #[clap(
-        short = 'u',
         long = "enr-v6-port",
         help = "Specifies the UDP port of the ENR record corresponding to ipv6 address. Not specifying this results in an ENR with no UDP field, unless the -w switch is used."
     )]
@@ -78,6 +198,12 @@ pub struct Server {
     #[clap(
         short = 'w',
         long = "enr-default",
+        value_name = "BOOL",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value = "false",
+        default_missing_value = "true",
         help = "The Enr IP address and port will be the same as the specified listening address and port."
     )]
     pub enr_default: bool,
@@ -85,6 +211,12 @@ pub struct Server {
     #[clap(
         short = 'k',
         long = "static-key",
+        value_name = "BOOL",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value = "false",
+        default_missing_value = "true",
         help = "Use a fixed static key (hard-coded). This is primarily for debugging."
     )]
     pub static_key: bool,
@@ -131,6 +263,12 @@ pub struct Server {
     #[clap(
         short = 'x',
         long = "no-search",
+        value_name = "BOOL",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value = "false",
+        default_missing_value = "true",
         help = "Prevents the server from doing any peer searches."
     )]
     pub no_search: bool,
@@ -141,4 +279,245 @@ pub struct Server {
         help = "Bootstraps the server peers from a specified file."
     )]
     pub bootstrap: Option<String>,
+    /// A directory to persist the node key, the last published ENR and the routing table across
+    /// restarts.
+    #[clap(
+        long = "data-dir",
+        help = "A directory to persist the node key, the last published ENR and the routing table. The ENR sequence number is reused and incremented whenever the record changes, and the stored routing table is restored before --enr and --bootstrap are added."
+    )]
+    pub data_dir: Option<String>,
+    /// The number of seconds between saves of the routing table to the data directory.
+    #[clap(
+        long = "table-save-interval",
+        help = "The number of seconds between saves of the routing table to --data-dir. The table is always saved on shutdown, 0 only saves it then.",
+        default_value = "60"
+    )]
+    pub table_save_interval: u64,
+    /// Files to export the discovered nodes to.
+    #[clap(
+        long = "export",
+        value_name = "FILE",
+        value_parser = ExportFormat::parse_path,
+        help = "Exports every node the server discovers to a file on shutdown and every --export-interval seconds. The format follows the extension: .csv, .jsonl, a .dot or .graphml peer graph of a crawl or queries, or a .json bootstrap file for --bootstrap. Can be given multiple times."
+    )]
+    pub export: Vec<String>,
+    /// The number of seconds between exports of the discovered nodes.
+    #[clap(
+        long = "export-interval",
+        help = "The number of seconds between exports of the discovered nodes. 0 only exports them on shutdown.",
+        default_value = "60"
+    )]
+    pub export_interval: u64,
+    /// The maximum number of discovered nodes kept for export.
+    #[clap(
+        long = "export-max-nodes",
+        help = "The maximum number of discovered nodes kept for --export. Beyond it, the least recently seen nodes are dropped.",
+        default_value = "100000",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub export_max_nodes: u64,
+    /// Answers TALK requests of a protocol with a handler.
+    #[clap(
+        long = "talk",
+        value_name = "PROTOCOL=HANDLER",
+        help = "Answers TALK requests of a protocol (utf8, 0x prefixed hex or * for any) with a handler: echo, static:<hex>, exec:<command> or unix:<path>. External handlers receive a JSON line per request and reply with a hex line. Can be given multiple times, the first matching protocol is used."
+    )]
+    pub talk: Vec<String>,
+    /// How long a TALK handler may take to answer.
+    #[clap(
+        long = "talk-timeout",
+        help = "The number of milliseconds a --talk handler may take to answer a request before an empty response is sent. A process handler that times out is restarted for the next request.",
+        default_value = "500"
+    )]
+    pub talk_timeout: u64,
+    /// Only reports and keeps the nodes of a consensus network.
+    #[clap(
+        long = "fork-digest",
+        help = "Only reports and keeps nodes whose eth2 field has this hex encoded fork digest. Lookups use the digest as a predicate, the routing table rejects other nodes and the rejected nodes are counted per foreign fork digest."
+    )]
+    pub fork_digest: Option<String>,
+    /// The address of the HTTP/JSON admin API.
+    #[clap(
+        long = "http-addr",
+        help = "Serves the HTTP/JSON admin API on this address, for example 127.0.0.1:5052."
+    )]
+    pub http_addr: Option<SocketAddr>,
+    /// The address of the Prometheus metrics endpoint.
+    #[clap(
+        long = "metrics-addr",
+        help = "Serves Prometheus metrics at /metrics on this address. May be the same as --http-addr."
+    )]
+    pub metrics_addr: Option<SocketAddr>,
+    /// Settings of the discv5 protocol.
+    #[clap(flatten, next_help_heading = "Discv5 Options")]
+    #[serde(flatten)]
+    pub discv5: Discv5Tuning,
+}
+
+/// The nodes admitted to the routing table.
+#[derive(
+    ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TableFilter {
+    /// Any node
+    #[default]
+    Any,
+    /// Nodes with an ipv4 UDP socket
+    Ip4,
+    /// Nodes with an ipv6 UDP socket
+    Ip6,
+    /// Nodes advertising a publicly routable address
+    Public,
+}
+
+/// Discv5 protocol settings of the server.
+#[derive(Args, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub struct Discv5Tuning {
+    /// The time to wait for a response to a request.
+    #[clap(
+        long = "request-timeout",
+        help = "The number of seconds to wait for a response to a request.",
+        default_value = "3"
+    )]
+    pub request_timeout: u64,
+    /// The time votes on our external address are valid for.
+    #[clap(
+        long = "vote-duration",
+        help = "The number of seconds votes of peers on our external address are valid for.",
+        default_value = "120"
+    )]
+    pub vote_duration: u64,
+    /// The number of peers queried in parallel.
+    #[clap(
+        long = "query-parallelism",
+        help = "The number of peers contacted in parallel by a query.",
+        default_value = "3"
+    )]
+    pub query_parallelism: usize,
+    /// The time a query waits for a peer to respond.
+    #[clap(
+        long = "query-peer-timeout",
+        help = "The number of seconds a query waits for a peer before contacting others.",
+        default_value = "2"
+    )]
+    pub query_peer_timeout: u64,
+    /// The time after which a query is stopped.
+    #[clap(
+        long = "query-timeout",
+        help = "The number of seconds after which a query is stopped.",
+        default_value = "60"
+    )]
+    pub query_timeout: u64,
+    /// The number of established sessions kept.
+    #[clap(
+        long = "session-cache-capacity",
+        help = "The number of established sessions kept before the least recently used is dropped.",
+        default_value = "1000"
+    )]
+    pub session_cache_capacity: usize,
+    /// The time a session is kept for.
+    #[clap(
+        long = "session-timeout",
+        help = "The number of seconds an established session is kept for.",
+        default_value = "86400"
+    )]
+    pub session_timeout: u64,
+    /// The time between pings to connected nodes.
+    #[clap(
+        long = "ping-interval",
+        help = "The number of seconds between pings to connected nodes.",
+        default_value = "300"
+    )]
+    pub ping_interval: u64,
+    /// The nodes admitted to the routing table.
+    #[clap(
+        long = "table-filter",
+        help = "The nodes admitted to the routing table.",
+        default_value = "any"
+    )]
+    pub table_filter: TableFilter,
+    /// The maximum number of nodes that contacted us per bucket.
+    #[clap(
+        long = "incoming-bucket-limit",
+        help = "The maximum number of nodes per bucket that contacted us, at most 16.",
+        default_value = "8"
+    )]
+    pub incoming_bucket_limit: usize,
+    /// Limits the number of nodes of a /24 subnet in the routing table.
+    #[clap(
+        long = "ip-limit",
+        value_name = "BOOL",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value = "false",
+        default_missing_value = "true",
+        help = "Limits the number of nodes of a /24 subnet in the routing table to mitigate eclipse attacks."
+    )]
+    pub ip_limit: bool,
+    /// The time nodes are banned for.
+    #[clap(
+        long = "ban-duration",
+        help = "The number of seconds misbehaving nodes are banned for. 0 bans them indefinitely.",
+        default_value = "3600"
+    )]
+    pub ban_duration: u64,
+    /// Enables the inbound packet filter.
+    #[clap(
+        long = "packet-filter",
+        value_name = "BOOL",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value = "false",
+        default_missing_value = "true",
+        help = "Enables the inbound packet filter, which applies the --filter-* limits."
+    )]
+    pub packet_filter: bool,
+    /// The number of requests accepted per second.
+    #[clap(
+        long = "filter-total-rate",
+        help = "The number of requests accepted per second. 0 disables rate limiting.",
+        default_value = "10"
+    )]
+    pub filter_total_rate: u64,
+    /// The number of requests accepted per second from a node.
+    #[clap(
+        long = "filter-node-rate",
+        help = "The number of requests accepted per second from a single node id. 0 disables the limit.",
+        default_value = "8"
+    )]
+    pub filter_node_rate: u64,
+    /// The number of requests accepted per second from an IP address.
+    #[clap(
+        long = "filter-ip-rate",
+        help = "The number of requests accepted per second from a single IP address. 0 disables the limit.",
+        default_value = "9"
+    )]
+    pub filter_ip_rate: u64,
+    /// The number of node ids per IP address before it is banned.
+    #[clap(
+        long = "filter-max-nodes-per-ip",
+        help = "The number of node ids allowed per IP address before the address is banned. 0 disables the limit.",
+        default_value = "10"
+    )]
+    pub filter_max_nodes_per_ip: usize,
+    /// The number of banned nodes per IP address before it is banned.
+    #[clap(
+        long = "filter-max-bans-per-ip",
+        help = "The number of banned nodes allowed per IP address before the address is banned. 0 disables the limit.",
+        default_value = "5"
+    )]
+    pub filter_max_bans_per_ip: usize,
+}
+
+impl Default for Discv5Tuning {
+    /// The defaults of the command line options.
+    fn default() -> Self {
+        let matches =
+            Discv5Tuning::augment_args(Command::new("discv5")).get_matches_from(["discv5"]);
+        Discv5Tuning::from_arg_matches(&matches).expect("The option defaults are valid")
+    }
 }
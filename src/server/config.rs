@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, path::Path};
+
+use clap::{Command, ValueEnum};
+use serde_json::Value;
+
+use super::Server;
+
+/// The format of a server configuration file.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ConfigFormat {
+    /// TOML
+    #[default]
+    Toml,
+    /// YAML
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// The format of a file based on its extension. Files that are not `.yaml` or `.yml` are read
+    /// as TOML.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+}
+
+/// The key of the service to run.
+const SERVICE_KEY: &str = "service";
+
+/// Reads the options of a configuration file, keyed by their long flag name.
+pub fn load(path: &Path) -> eyre::Result<BTreeMap<String, Value>> {
+    let contents = std::fs::read_to_string(path)?;
+    let values = match ConfigFormat::from_path(path) {
+        ConfigFormat::Toml => toml::from_str(&contents)?,
+        ConfigFormat::Yaml => serde_yaml::from_str(&contents)?,
+    };
+    Ok(values)
+}
+
+/// Sets the values of a configuration file as the defaults of the server command's arguments,
+/// so flags given on the command line take precedence. The `service` key is read by
+/// [`service_args`].
+///
+/// Keys are the long flag names, with either dashes or underscores.
+pub fn apply(command: Command, values: BTreeMap<String, Value>) -> eyre::Result<Command> {
+    let mut defaults = BTreeMap::new();
+    for (key, value) in values {
+        let id = key.replace('-', "_");
+        if id == SERVICE_KEY {
+            continue;
+        }
+        if id == "config" {
+            eyre::bail!("{key} can only be given on the command line");
+        }
+        let values = match value {
+            Value::Null => continue,
+            Value::Array(values) => values
+                .into_iter()
+                .map(|value| to_arg_value(&key, value))
+                .collect::<eyre::Result<Vec<_>>>()?,
+            value => vec![to_arg_value(&key, value)?],
+        };
+        defaults.insert(id, values);
+    }
+
+    let server = command
+        .find_subcommand("server")
+        .ok_or_else(|| eyre::eyre!("The server command is not defined"))?;
+    if let Some(unknown) = defaults.keys().find(|id| {
+        !server
+            .get_arguments()
+            .any(|arg| arg.get_id() == id.as_str() && !arg.is_positional())
+    }) {
+        eyre::bail!("Unknown server option {unknown}");
+    }
+
+    Ok(command.mut_subcommand("server", |server| {
+        server.mut_args(|arg| match defaults.get(arg.get_id().as_str()) {
+            Some(values) => arg.default_values(values.clone()),
+            None => arg,
+        })
+    }))
+}
+
+/// The arguments selecting the service of a configuration file, to be appended to the command line
+/// if it does not select a service itself. Empty if the file has no `service`.
+///
+/// The service is either its name, such as `service = "events"`, or a table of the name and
+/// its options keyed by their long flag name, such as `[service.crawl]` with `output = "a.json"`.
+pub fn service_args(
+    command: &Command,
+    values: &BTreeMap<String, Value>,
+) -> eyre::Result<Vec<String>> {
+    let Some(service) = values.get(SERVICE_KEY) else {
+        return Ok(Vec::new());
+    };
+    let (name, options) = match service {
+        Value::String(name) => (name.clone(), serde_json::Map::new()),
+        Value::Object(table) if table.len() == 1 => {
+            let (name, options) = table.iter().next().expect("The table has one entry");
+            match options {
+                Value::Object(options) => (name.clone(), options.clone()),
+                Value::Null => (name.clone(), serde_json::Map::new()),
+                _ => eyre::bail!("Invalid options for service {name}, expected a table"),
+            }
+        }
+        _ => {
+            eyre::bail!("Invalid service, expected a service name or a table with a single service")
+        }
+    };
+
+    let service = command
+        .find_subcommand("server")
+        .and_then(|server| server.find_subcommand(&name))
+        .ok_or_else(|| eyre::eyre!("Unknown service {name}"))?;
+    let mut args = vec![name.clone()];
+    for (key, value) in options {
+        let id = key.replace('-', "_");
+        let long = service
+            .get_arguments()
+            .find(|arg| arg.get_id() == id.as_str())
+            .and_then(|arg| arg.get_long())
+            .ok_or_else(|| eyre::eyre!("Unknown {name} option {key}"))?;
+        let values = match value {
+            Value::Null => continue,
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            args.push(format!("--{long}={}", to_arg_value(&key, value)?));
+        }
+    }
+    Ok(args)
+}
+
+/// Serializes the effective server configuration in the format of a configuration file.
+pub fn to_string(server: &Server, format: ConfigFormat) -> eyre::Result<String> {
+    Ok(match format {
+        ConfigFormat::Toml => toml::to_string(server)?,
+        ConfigFormat::Yaml => serde_yaml::to_string(server)?,
+    })
+}
+
+/// Converts a configuration value into the string clap parses.
+fn to_arg_value(key: &str, value: Value) -> eyre::Result<String> {
+    match value {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => eyre::bail!("Invalid value for {key}, expected a string, number or boolean"),
+    }
+}
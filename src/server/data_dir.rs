@@ -0,0 +1,112 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use discv5::enr::{CombinedKey, Enr};
+
+use super::bootstrap::BootstrapStore;
+use crate::keys::{format, KeyFormat, KeyType};
+
+/// The file name of the persisted ENR.
+pub const ENR_FILENAME: &str = "enr.dat";
+
+/// The file name of the persisted node key.
+pub const KEY_FILENAME: &str = "key";
+
+/// The file name of the persisted routing table.
+pub const TABLE_FILENAME: &str = "table.json";
+
+/// Loads the hex encoded secp256k1 node key from the data directory, if one has been stored.
+pub fn load_key(dir: impl AsRef<Path>) -> eyre::Result<Option<CombinedKey>> {
+    let path = dir.as_ref().join(KEY_FILENAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let key = format::decode_str(
+        fs::read_to_string(&path)?.trim(),
+        KeyFormat::Hex,
+        KeyType::Secp256k1,
+    )
+    .map_err(|e| eyre::eyre!("{e} in {}", path.display()))?;
+    Ok(Some(key))
+}
+
+/// Stores the node key in the data directory as hex. The file is only readable by the owner and
+/// is replaced atomically.
+pub fn save_key(dir: impl AsRef<Path>, key: &CombinedKey) -> eyre::Result<()> {
+    let path = prepare(dir.as_ref(), KEY_FILENAME)?;
+    write_atomic(&path, hex::encode(key.encode()).as_bytes(), true)
+}
+
+/// Loads the last published ENR from the data directory, if one has been stored.
+pub fn load_enr(dir: impl AsRef<Path>) -> eyre::Result<Option<Enr<CombinedKey>>> {
+    let path = dir.as_ref().join(ENR_FILENAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let enr = Enr::from_str(fs::read_to_string(&path)?.trim())
+        .map_err(|e| eyre::eyre!("Invalid ENR in {}: {e}", path.display()))?;
+    Ok(Some(enr))
+}
+
+/// Stores the ENR in the data directory as base64.
+pub fn save_enr(dir: impl AsRef<Path>, enr: &Enr<CombinedKey>) -> eyre::Result<()> {
+    let path = prepare(dir.as_ref(), ENR_FILENAME)?;
+    write_atomic(&path, enr.to_base64().as_bytes(), false)
+}
+
+/// Loads the routing table from the data directory, if one has been stored.
+pub fn load_table(dir: impl AsRef<Path>) -> eyre::Result<Option<BootstrapStore>> {
+    let path = dir.as_ref().join(TABLE_FILENAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let table = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| eyre::eyre!("Invalid routing table in {}: {e}", path.display()))?;
+    Ok(Some(table))
+}
+
+/// Stores the routing table in the data directory in the format of a bootstrap file. The file is
+/// replaced atomically, so an interrupted save keeps the previous table.
+pub fn save_table(dir: impl AsRef<Path>, table: &BootstrapStore) -> eyre::Result<()> {
+    let path = prepare(dir.as_ref(), TABLE_FILENAME)?;
+    write_atomic(
+        &path,
+        serde_json::to_string_pretty(table)?.as_bytes(),
+        false,
+    )
+}
+
+/// Creates the data directory if required and returns the path of the file within it.
+fn prepare(dir: &Path, filename: &str) -> eyre::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    Ok(dir.join(filename))
+}
+
+/// Writes the file through a temporary file in the same directory that is renamed over it, so
+/// readers never see a partial file. Private files are created with owner-only permissions on unix.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8], private: bool) -> eyre::Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    // A leftover partial file may have been created with other permissions
+    let _ = fs::remove_file(&partial);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options.open(&partial)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(partial, path)?;
+    Ok(())
+}
@@ -2,6 +2,8 @@ use std::net::IpAddr;
 
 use discv5::enr;
 
+use crate::utils::listen;
+
 /// Builds an Enr from Server input cli args.
 pub fn build(
     server: &super::command::Server,
@@ -10,17 +12,7 @@ pub fn build(
     let mut builder = enr::Builder::default();
 
     // Extract params from server config
-    let mut ipv4_address = None;
-    let mut ipv6_address = None;
-    for address in server.listen_addresses.split(',') {
-        match address
-            .parse::<IpAddr>()
-            .expect("Invalid listening address")
-        {
-            IpAddr::V4(ip) => ipv4_address = Some(ip),
-            IpAddr::V6(ip) => ipv6_address = Some(ip),
-        }
-    }
+    let (ipv4_address, ipv6_address) = listen::parse_addresses(&server.listen_addresses)?;
 
     let listen_port = server.listen_port;
     let listen_port_v6 = server.listen_port_v6;
@@ -78,7 +70,7 @@ pub fn build(
     // Set the eth2 enr field.
     if let Some(eth2_string) = &server.enr_eth2 {
         let ssz_bytes = hex::decode(eth2_string).expect("Invalid eth2 hex bytes");
-        builder.add_value("eth2", &ssz_bytes);
+        builder.add_value("eth2", &ssz_bytes.as_slice());
     }
 
     // Build
@@ -86,16 +78,55 @@ pub fn build(
 
     // If the ENR is useful print it
     log::info!("Node Id: {}", enr.node_id());
-    if enr.udp4_socket().is_some() {
+    if enr.udp4_socket().is_some() || enr.udp6_socket().is_some() {
         log::info!("Base64 ENR: {}", enr.to_base64());
-        log::info!(
-            "ip: {}, udp port:{}",
-            enr.ip4().unwrap(),
-            enr.udp4().unwrap()
-        );
+        if let Some(socket) = enr.udp4_socket() {
+            log::info!("ip: {}, udp port:{}", socket.ip(), socket.port());
+        }
+        if let Some(socket) = enr.udp6_socket() {
+            log::info!("ip6: {}, udp6 port:{}", socket.ip(), socket.port());
+        }
     } else {
         log::warn!("ENR is not printed as no IP:PORT was specified");
     }
 
     Ok(enr)
 }
+
+/// Carries the sequence number of a previously published ENR over to a freshly built one.
+///
+/// If the content of the record is unchanged the previous sequence number is reused, otherwise
+/// it is incremented so peers accept the new record. An explicit `--enr-seq-no` takes precedence.
+pub fn reconcile_seq(
+    server: &super::command::Server,
+    enr: &mut enr::Enr<enr::CombinedKey>,
+    enr_key: &enr::CombinedKey,
+    previous: Option<&enr::Enr<enr::CombinedKey>>,
+) -> eyre::Result<()> {
+    let Some(previous) = previous else {
+        return Ok(());
+    };
+    if server.enr_seq_no.is_some() {
+        if enr.seq() <= previous.seq() {
+            log::warn!(
+                "Specified sequence number {} is not greater than the stored one {}",
+                enr.seq(),
+                previous.seq()
+            );
+        }
+        return Ok(());
+    }
+    if previous.node_id() != enr.node_id() {
+        log::warn!("Stored ENR belongs to a different node id, ignoring its sequence number");
+        return Ok(());
+    }
+
+    enr.set_seq(previous.seq(), enr_key)?;
+    if !enr.compare_content(previous) {
+        enr.set_seq(previous.seq() + 1, enr_key)?;
+        log::info!("ENR has changed, sequence number updated to {}", enr.seq());
+    } else {
+        log::info!("ENR is unchanged, reusing sequence number {}", enr.seq());
+    }
+    Ok(())
+}
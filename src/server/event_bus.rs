@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use discv5::{enr::NodeId, Discv5, Event, TalkRequest};
+use tokio::sync::{broadcast, mpsc};
+
+/// The number of events buffered per subscriber before it starts to lag.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Fans the discv5 event stream out to every service that needs it.
+///
+/// Discv5 only supports a single event stream, requesting a new one closes the previous stream.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<BusEvent>>,
+}
+
+/// An event forwarded by the [`EventBus`].
+#[derive(Debug)]
+pub enum BusEvent {
+    /// An event of the discv5 event stream.
+    Discv5(Event),
+    /// A TALK request that was handed to the TALK responder, which owns the request.
+    TalkRequest {
+        /// The node that sent the request.
+        node_id: NodeId,
+        /// The protocol of the request.
+        protocol: Vec<u8>,
+    },
+}
+
+impl BusEvent {
+    /// The discv5 event, if this is not a TALK request handed to the responder.
+    pub fn discv5(&self) -> Option<&Event> {
+        match self {
+            BusEvent::Discv5(event) => Some(event),
+            BusEvent::TalkRequest { .. } => None,
+        }
+    }
+}
+
+/// A subscription to the [`EventBus`].
+pub struct EventReceiver {
+    receiver: broadcast::Receiver<Arc<BusEvent>>,
+}
+
+impl EventBus {
+    /// Takes the discv5 event stream and forwards every event to the subscribers.
+    ///
+    /// A TALK request can only be answered by its owner, so if a `talk_requests` channel is given
+    /// TALK requests are sent there, and the subscribers receive a [`BusEvent::TalkRequest`]
+    /// describing them instead. The discv5 server must be started.
+    pub async fn start(
+        discv5: &Discv5,
+        talk_requests: Option<mpsc::Sender<TalkRequest>>,
+    ) -> eyre::Result<Self> {
+        let mut event_stream = discv5
+            .event_stream()
+            .await
+            .map_err(|e| eyre::eyre!("Failed to obtain the event stream: {e:?}"))?;
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let bus = EventBus { sender };
+
+        let forward = bus.sender.clone();
+        tokio::spawn(async move {
+            while let Some(event) = event_stream.recv().await {
+                let event = match (event, &talk_requests) {
+                    (Event::TalkRequest(request), Some(talk_requests)) => {
+                        let diverted = BusEvent::TalkRequest {
+                            node_id: *request.node_id(),
+                            protocol: request.protocol().to_vec(),
+                        };
+                        // A full channel drops the request, which sends an empty response
+                        let _ = talk_requests.try_send(request);
+                        diverted
+                    }
+                    (event, _) => BusEvent::Discv5(event),
+                };
+                // An error only means there are currently no subscribers.
+                let _ = forward.send(Arc::new(event));
+            }
+        });
+
+        Ok(bus)
+    }
+
+    /// Subscribes to all events emitted from now on.
+    pub fn subscribe(&self) -> EventReceiver {
+        EventReceiver {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+impl EventReceiver {
+    /// Receives the next event. Returns `None` once the discv5 server has shut down.
+    pub async fn recv(&mut self) -> Option<Arc<BusEvent>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::debug!("Event subscriber lagged, skipped {skipped} events");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    convert::TryInto,
+};
+
+use discv5::enr::{self, NodeId};
+use parking_lot::Mutex;
+
+use crate::utils::eth2::EnrForkId;
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The number of most recently rejected node ids remembered to count each node once.
+const RECENT_REJECTIONS: usize = 10_000;
+
+/// The number of foreign fork digests counted separately. Nodes of further digests are counted
+/// as `other`.
+const MAX_FORK_DIGESTS: usize = 256;
+
+/// Accepts the nodes of a single consensus network, counting the rejected nodes per foreign fork
+/// digest.
+#[derive(Debug)]
+pub struct ForkDigestFilter {
+    fork_digest: [u8; 4],
+    rejected: Mutex<Rejected>,
+}
+
+/// The nodes rejected by a [`ForkDigestFilter`], in bounded memory.
+#[derive(Debug, Default)]
+struct Rejected {
+    /// The number of rejected nodes per fork digest, `none`, `invalid` or `other`.
+    counts: HashMap<String, usize>,
+    /// The most recently rejected node ids, which are not counted again.
+    recent: HashSet<NodeId>,
+    /// The order `recent` was filled in, to forget the oldest node ids first.
+    order: VecDeque<NodeId>,
+}
+
+impl Rejected {
+    /// Counts a rejected node under its fork digest, unless it was rejected recently.
+    fn insert(&mut self, digest: String, node_id: NodeId) {
+        if !self.recent.insert(node_id) {
+            return;
+        }
+        self.order.push_back(node_id);
+        if self.order.len() > RECENT_REJECTIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.recent.remove(&oldest);
+            }
+        }
+        let digest = if self.counts.contains_key(&digest) || self.counts.len() < MAX_FORK_DIGESTS {
+            digest
+        } else {
+            "other".to_string()
+        };
+        *self.counts.entry(digest).or_default() += 1;
+    }
+}
+
+impl ForkDigestFilter {
+    /// Creates a filter accepting nodes with the fork digest.
+    pub fn new(fork_digest: [u8; 4]) -> Self {
+        ForkDigestFilter {
+            fork_digest,
+            rejected: Mutex::new(Rejected::default()),
+        }
+    }
+
+    /// The accepted fork digest.
+    pub fn fork_digest(&self) -> [u8; 4] {
+        self.fork_digest
+    }
+
+    /// Whether the `eth2` field of the ENR has the accepted fork digest. Rejected nodes are
+    /// counted under their fork digest, or `none` and `invalid` for ENRs without a valid `eth2`
+    /// field. A node is counted again if it is rejected after many other nodes, and the digests
+    /// beyond the first 256 are counted as `other`.
+    pub fn matches(&self, enr: &Enr) -> bool {
+        let digest = match EnrForkId::from_enr(enr) {
+            Some(Ok(fork_id)) if fork_id.fork_digest == self.fork_digest => return true,
+            Some(Ok(fork_id)) => format!("0x{}", hex::encode(fork_id.fork_digest)),
+            Some(Err(_)) => "invalid".to_string(),
+            None => "none".to_string(),
+        };
+        self.rejected.lock().insert(digest, enr.node_id());
+        false
+    }
+
+    /// The number of rejected nodes per fork digest, `none`, `invalid` or `other`.
+    pub fn rejected(&self) -> BTreeMap<String, usize> {
+        self.rejected
+            .lock()
+            .counts
+            .iter()
+            .map(|(digest, nodes)| (digest.clone(), *nodes))
+            .collect()
+    }
+}
+
+/// Parses a 4 byte hex encoded fork digest, with or without a `0x` prefix.
+pub fn parse(fork_digest: &str) -> Result<[u8; 4], String> {
+    let bytes = hex::decode(fork_digest.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid fork digest {fork_digest}: {e}"))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("Invalid fork digest {fork_digest}: expected 4 bytes"))
+}
@@ -1,7 +1,8 @@
 use discv5::{enr::k256, enr::CombinedKey};
 
 /// Generates a [discv5] [CombinedKey](discv5::enr::CombinedKey) from the server config.
-/// If the static_key flag is set, a fixed key is used for testing.
+/// If the static_key flag is set, a fixed key is used for testing. Otherwise, when a data
+/// directory is given, the key stored there is reused, or a new one is generated and stored.
 pub fn generate(server: &super::command::Server) -> eyre::Result<CombinedKey> {
     if server.static_key {
         let raw_key = vec![
@@ -16,6 +17,13 @@ pub fn generate(server: &super::command::Server) -> eyre::Result<CombinedKey> {
         let secret_key = k256::ecdsa::SigningKey::from_slice(&raw_key)
             .map_err(|_| eyre::eyre!("Invalid secp256k1 key"))?;
         Ok(CombinedKey::from(secret_key))
+    } else if let Some(data_dir) = &server.data_dir {
+        if let Some(key) = super::data_dir::load_key(data_dir)? {
+            return Ok(key);
+        }
+        let key = CombinedKey::generate_secp256k1();
+        super::data_dir::save_key(data_dir, &key)?;
+        Ok(key)
     } else {
         Ok(CombinedKey::generate_secp256k1())
     }
@@ -1,12 +1,14 @@
-use discv5::{enr, ConfigBuilder, Discv5, ListenConfig};
+use discv5::{enr, ConfigBuilder, Discv5};
 use std::{
     convert::TryInto,
-    net::{IpAddr, SocketAddrV4, SocketAddrV6},
+    path::Path,
     process::exit,
-    sync::Arc,
+    sync::{Arc, OnceLock},
     time::Duration,
 };
 
+use crate::utils::{listen, local_node::LocalNode};
+
 /// Services
 pub mod services;
 
@@ -19,53 +21,144 @@ pub mod enr_build;
 /// Key construction for the server.
 pub mod keys;
 
+/// Persistence of server state in a data directory.
+pub mod data_dir;
+
+/// Fans the discv5 event stream out to the services.
+pub mod event_bus;
+
+/// Server options read from a configuration file.
+pub mod config;
+
+/// Discv5 protocol settings of the server.
+pub mod tuning;
+
+/// Filtering of nodes by the fork digest of their eth2 field.
+pub mod fork_digest;
+
 /// The [clap] cli command arguments for the server service.
 pub mod command;
 pub use command::*;
 
+/// The routing table filter of the server with `--fork-digest`. Discv5 only accepts a plain
+/// function as the table filter, so the fork digest filter and the table filter it is combined
+/// with are kept here.
+static TABLE_FILTER: OnceLock<(EnrFilter, Arc<fork_digest::ForkDigestFilter>)> = OnceLock::new();
+
+/// A routing table filter.
+type EnrFilter = fn(&enr::Enr<enr::CombinedKey>) -> bool;
+
+/// Installs the fork digest filter for the routing table in addition to the `--table-filter`, and
+/// returns the function to configure discv5 with. Fails if a server of this process already
+/// installed a filter.
+fn fork_digest_table_filter(
+    server: &Server,
+    filter: Arc<fork_digest::ForkDigestFilter>,
+) -> eyre::Result<EnrFilter> {
+    let base = tuning::table_filter(server.discv5.table_filter);
+    TABLE_FILTER.set((base, filter)).map_err(|_| {
+        eyre::eyre!("Only one server with a fork digest filter can run in a process")
+    })?;
+    Ok(|enr| match TABLE_FILTER.get() {
+        Some((base, filter)) => base(enr) && filter.matches(enr),
+        None => true,
+    })
+}
+
 /// Run the query server
+///
+/// The `--fork-digest` filter of the routing table is installed for the whole process, so only
+/// the first server of a process can be started with it.
 pub async fn run(server: &Server) {
+    if let ServerSubcommand::PrintConfig(print_config) = &server.service {
+        match config::to_string(server, print_config.format) {
+            Ok(config) => print!("{config}"),
+            Err(e) => log::error!("Failed to print the configuration. Error: {}", e),
+        }
+        return;
+    }
+
     // The number of nodes required to come to consensus before our external IP is updated.
     let peer_update_min = server.peer_update_min;
 
     // Build the ENR
     let enr_key = keys::generate(server).unwrap();
-    let enr = enr_build::build(server, &enr_key).unwrap();
+    let mut enr = enr_build::build(server, &enr_key).unwrap();
+
+    // Reuse or bump the sequence number of the previously published ENR
+    if let Some(data_dir) = &server.data_dir {
+        let previous = data_dir::load_enr(data_dir).unwrap_or_else(|e| {
+            log::warn!("Failed to load the stored ENR: {e}");
+            None
+        });
+        enr_build::reconcile_seq(server, &mut enr, &enr_key, previous.as_ref()).unwrap();
+        if let Err(e) = data_dir::save_enr(data_dir, &enr) {
+            log::warn!("Failed to store the ENR: {e}");
+        }
+    }
 
     let connect_enr = server.enr.as_ref().map(|enr| {
         enr.parse::<enr::Enr<enr::CombinedKey>>()
             .expect("Invalid base64 encoded ENR")
     });
 
-    let mut ipv4_address = None;
-    let mut ipv6_address = None;
-    for address in server.listen_addresses.split(',') {
-        match address
-            .parse::<IpAddr>()
-            .expect("Invalid listening address")
-        {
-            IpAddr::V4(ip) => ipv4_address = Some(ip),
-            IpAddr::V6(ip) => ipv6_address = Some(ip),
-        }
-    }
-
-    let listen_port = server.listen_port;
-    let listen_port_v6 = server.listen_port_v6;
-
-    let listen_config = ListenConfig::from_two_sockets(
-        ipv4_address.map(|v| SocketAddrV4::new(v, listen_port)),
-        ipv6_address.map(|v| SocketAddrV6::new(v, listen_port_v6.unwrap_or(listen_port), 0, 0)),
+    let (ipv4_address, ipv6_address) =
+        listen::parse_addresses(&server.listen_addresses).expect("Invalid listening address");
+    let listen_config = listen::listen_config(
+        ipv4_address,
+        ipv6_address,
+        server.listen_port,
+        server.listen_port_v6,
     );
 
+    let fork_digest_filter = server.fork_digest.as_ref().map(|fork_digest| {
+        let fork_digest = fork_digest::parse(fork_digest).expect("Invalid fork digest");
+        Arc::new(fork_digest::ForkDigestFilter::new(fork_digest))
+    });
+    let table_filter = match &fork_digest_filter {
+        Some(filter) => {
+            log::info!(
+                "Only keeping nodes with fork digest 0x{}",
+                hex::encode(filter.fork_digest())
+            );
+            match fork_digest_table_filter(server, Arc::clone(filter)) {
+                Ok(table_filter) => Some(table_filter),
+                Err(e) => {
+                    log::error!("{e}");
+                    exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
     log::info!("Server listening on {:?}", listen_config);
-    // Build the discv5 server using a default config
-    let config = ConfigBuilder::new(listen_config)
-        .request_timeout(Duration::from_secs(3))
-        .vote_duration(Duration::from_secs(120))
-        .enr_peer_update_min(peer_update_min.try_into().unwrap())
-        .build();
+    // Build the discv5 server using the configured protocol settings
+    let mut config = ConfigBuilder::new(listen_config);
+    config.enr_peer_update_min(peer_update_min.try_into().unwrap());
+    tuning::configure(&server.discv5, &mut config).expect("Invalid discv5 settings");
+    if let Some(table_filter) = table_filter {
+        config.table_filter(table_filter);
+    }
+    let config = config.build();
     let mut discv5 = Discv5::new(enr, enr_key, config).unwrap();
 
+    // Restore the routing table of the previous run
+    if let Some(data_dir) = &server.data_dir {
+        match data_dir::load_table(data_dir) {
+            Ok(Some(table)) => {
+                let restored = table.add_to_table(&discv5);
+                log::info!(
+                    "Restored {} of {} stored routing table entries",
+                    restored,
+                    table.data.len()
+                );
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to load the stored routing table: {e}"),
+        }
+    }
+
     // Connect to an ENR if allowed to search for p2p connections
     if !server.no_search {
         if let Some(connect_enr) = connect_enr {
@@ -95,26 +188,257 @@ pub async fn run(server: &Server) {
         .await
         .expect("Should be able to start the server");
 
+    // Answer TALK requests if any handlers are configured
+    let talk_requests = if server.talk.is_empty() {
+        None
+    } else {
+        let routes = server
+            .talk
+            .iter()
+            .map(|route| route.parse::<services::talk::TalkRoute>())
+            .collect::<eyre::Result<Vec<_>>>()
+            .expect("Invalid talk handler");
+        let timeout = Duration::from_millis(server.talk_timeout);
+        Some(
+            services::talk::start(routes, timeout)
+                .expect("Should be able to start the talk handlers"),
+        )
+    };
+
     let server_ref = Arc::new(discv5);
+    let event_bus = event_bus::EventBus::start(&server_ref, talk_requests)
+        .await
+        .expect("Should be able to obtain the event stream");
+
+    // Serve the HTTP API and the metrics, on a single listener if they share an address
+    let metrics = server.metrics_addr.map(|_| {
+        let mut metrics = services::metrics::Metrics::new(Arc::clone(&server_ref));
+        if let Some(filter) = &fork_digest_filter {
+            metrics.register_fork_digest_filter(Arc::clone(filter));
+        }
+        let metrics = Arc::new(metrics);
+        services::metrics::run(Arc::clone(&metrics), event_bus.subscribe());
+        metrics
+    });
+    let mut listeners = Vec::new();
+    if let Some(http_addr) = server.http_addr {
+        if !http_addr.ip().is_loopback() {
+            log::warn!(
+                "The HTTP API on {} is not authenticated and can change the local ENR and routing table. Serve it on a loopback address unless the network is trusted.",
+                http_addr
+            );
+        }
+        listeners.push((
+            http_addr,
+            services::http::router(
+                Arc::clone(&server_ref),
+                fork_digest_filter.clone(),
+                metrics.clone(),
+            ),
+        ));
+    }
+    if let (Some(metrics_addr), Some(metrics)) = (server.metrics_addr, &metrics) {
+        let routes = services::metrics::router(Arc::clone(metrics));
+        match listeners.iter_mut().find(|(addr, _)| *addr == metrics_addr) {
+            Some((_, router)) => *router = router.clone().merge(routes),
+            None => listeners.push((metrics_addr, routes)),
+        }
+    }
+    for (addr, routes) in listeners {
+        services::http::serve(addr, routes)
+            .await
+            .expect("Should be able to start the HTTP server");
+    }
+
+    // Collect the discovered nodes to export them
+    let discoveries = (!server.export.is_empty()).then(|| {
+        let discoveries = Arc::new(services::export::Discoveries::new(
+            server.export_max_nodes.try_into().unwrap_or(usize::MAX),
+        ));
+        services::export::run(Arc::clone(&discoveries), event_bus.subscribe());
+        discoveries
+    });
+    if let (Some(discoveries), true) = (&discoveries, server.export_interval > 0) {
+        let discv5 = Arc::clone(&server_ref);
+        let discoveries = Arc::clone(discoveries);
+        let paths = server.export.clone();
+        let interval = Duration::from_secs(server.export_interval);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                export(&discv5, &discoveries, &paths);
+            }
+        });
+    }
+
     if server.stats > 0 {
-        services::stats::run(Arc::clone(&server_ref), None, server.stats);
+        services::stats::run(
+            Arc::clone(&server_ref),
+            event_bus.subscribe(),
+            None,
+            server.stats,
+        );
+    }
+
+    // Keep the stored ENR up to date as discv5 updates our socket
+    if let Some(data_dir) = server.data_dir.clone() {
+        let discv5 = Arc::clone(&server_ref);
+        let mut events = event_bus.subscribe();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                if let Some(discv5::Event::SocketUpdated(_)) = event.discv5() {
+                    let enr = discv5.local_enr();
+                    log::info!("Storing updated ENR with sequence number {}", enr.seq());
+                    if let Err(e) = data_dir::save_enr(&data_dir, &enr) {
+                        log::warn!("Failed to store the ENR: {e}");
+                    }
+                }
+            }
+        });
+    }
+
+    // Regularly store the routing table
+    if let (Some(data_dir), true) = (server.data_dir.clone(), server.table_save_interval > 0) {
+        let discv5 = Arc::clone(&server_ref);
+        let interval = Duration::from_secs(server.table_save_interval);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                save_table(&discv5, &data_dir);
+            }
+        });
     }
 
     if server.no_search {
         log::info!("Running without query service, press CTRL-C to exit.");
-        let _ = tokio::signal::ctrl_c().await;
+        shutdown_signal().await;
+        persist(server, &server_ref, discoveries.as_deref());
         exit(0);
     }
 
-    // Match on the subcommand and run the appropriate service
-    match server.service {
-        ServerSubcommand::Query => {
-            log::info!("Query service running...");
-            services::query::run(server_ref, Duration::from_secs(server.break_time)).await;
+    // Match on the subcommand and run the appropriate service until the server is stopped
+    let service = async {
+        match &server.service {
+            ServerSubcommand::Query => {
+                log::info!("Query service running...");
+                // Peer graphs need to know which node returned which
+                let peer_graph = discoveries
+                    .clone()
+                    .filter(|_| services::export::exports_peer_graph(&server.export))
+                    .map(|discoveries| services::query::PeerGraph {
+                        discoveries,
+                        local_node: request_node(server),
+                        parallelism: server.discv5.query_parallelism,
+                    });
+                services::query::run(
+                    Arc::clone(&server_ref),
+                    Duration::from_secs(server.break_time),
+                    metrics,
+                    fork_digest_filter,
+                    peer_graph,
+                )
+                .await;
+            }
+            ServerSubcommand::Events => {
+                log::info!("Events service running...");
+                services::events::run(event_bus.subscribe()).await;
+            }
+            ServerSubcommand::Crawl(crawl) => {
+                log::info!("Crawl service running...");
+                services::crawl::run(
+                    Arc::clone(&server_ref),
+                    &request_node(server),
+                    crawl,
+                    discoveries.clone(),
+                    metrics,
+                )
+                .await;
+            }
+            ServerSubcommand::SubnetSearch(search) => {
+                log::info!("Subnet search service running...");
+                services::subnets::run(
+                    Arc::clone(&server_ref),
+                    search,
+                    Duration::from_secs(server.break_time),
+                    fork_digest_filter,
+                    metrics,
+                )
+                .await;
+            }
+            ServerSubcommand::PrintConfig(_) => unreachable!("Handled before the server starts"),
+        }
+    };
+    tokio::select! {
+        _ = service => {}
+        _ = shutdown_signal() => log::info!("Shutting down..."),
+    }
+    persist(server, &server_ref, discoveries.as_deref());
+}
+
+/// The options of the short-lived nodes the crawl and peer graph lookups send their requests from,
+/// on free ports of the listening addresses.
+fn request_node(server: &Server) -> LocalNode {
+    LocalNode {
+        listen_addresses: server.listen_addresses.clone(),
+        listen_port: 0,
+        listen_port_v6: Some(0),
+    }
+}
+
+/// Stores the routing table and exports the discovered nodes before the server stops.
+fn persist(server: &Server, discv5: &Discv5, discoveries: Option<&services::export::Discoveries>) {
+    if let Some(data_dir) = &server.data_dir {
+        save_table(discv5, data_dir);
+    }
+    if let Some(discoveries) = discoveries {
+        export(discv5, discoveries, &server.export);
+    }
+}
+
+/// Stores the routing table in the data directory, logging failures.
+fn save_table(discv5: &Discv5, data_dir: &str) {
+    let table = bootstrap::BootstrapStore::from_table(discv5);
+    match data_dir::save_table(data_dir, &table) {
+        Ok(()) => log::debug!("Stored {} routing table entries", table.data.len()),
+        Err(e) => log::warn!("Failed to store the routing table: {e}"),
+    }
+}
+
+/// Exports the discovered nodes, including the routing table, to every export file, logging
+/// failures.
+fn export(discv5: &Discv5, discoveries: &services::export::Discoveries, paths: &[String]) {
+    discoveries.record_table(discv5);
+    let nodes = discoveries.nodes();
+    for path in paths.iter().map(Path::new) {
+        let format = match services::export::ExportFormat::from_path(path) {
+            Ok(format) => format,
+            Err(e) => {
+                log::warn!("{e}");
+                continue;
+            }
+        };
+        match services::export::write(path, format, &nodes) {
+            Ok(()) => log::debug!("Exported {} nodes to {}", nodes.len(), path.display()),
+            Err(e) => log::warn!("Failed to export the nodes to {}: {e}", path.display()),
         }
-        ServerSubcommand::Events => {
-            log::info!("Events service running...");
-            services::events::run(server_ref).await;
+    }
+}
+
+/// Resolves when the server is asked to stop with CTRL-C or, on unix, SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => log::warn!("Failed to listen for SIGTERM: {e}"),
         }
     }
+    let _ = tokio::signal::ctrl_c().await;
 }
@@ -0,0 +1,374 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use discv5::{
+    enr::{self, NodeId},
+    Discv5, Key,
+};
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    export::Discoveries,
+    metrics::{Metrics, QuerySource},
+};
+use crate::{
+    findnode,
+    request_enr::{failure::Failure, target::Target},
+    server::command::Crawl,
+    utils::{local_node::LocalNode, raw_node::RawNode},
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The largest log2 distance between two node ids.
+const MAX_DISTANCE: u64 = 256;
+
+/// The nodes discovered by a crawl.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CrawlDataset {
+    /// When the crawl started, in milliseconds since the unix epoch.
+    pub started_at: u64,
+    /// When the crawl finished, in milliseconds since the unix epoch.
+    pub finished_at: u64,
+    /// The discovered nodes, in the order they were discovered.
+    pub nodes: Vec<CrawledNode>,
+}
+
+/// A node discovered by a crawl.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CrawledNode {
+    /// The hex encoded node id.
+    pub node_id: String,
+    /// The base64 encoded ENR with the highest sequence number seen.
+    pub enr: String,
+    /// When the node was first discovered, in milliseconds since the unix epoch.
+    pub discovered_at: u64,
+    /// Whether the node answered a FINDNODE request.
+    pub responsive: bool,
+    /// Why the node did not answer, as a request-enr failure kind such as `no-response`.
+    pub failure: Option<String>,
+    /// The number of FINDNODE requests sent to the node.
+    pub requests: u32,
+    /// The number of distinct nodes the node returned.
+    pub returned: usize,
+    /// The hex encoded node ids of the nodes that returned this node. Empty for the nodes of the
+    /// local routing table the crawl started from.
+    pub found_by: Vec<String>,
+}
+
+/// The outcome of crawling a single node.
+struct Visit {
+    /// The FINDNODE requests sent.
+    requests: u32,
+    /// The returned nodes, or why the node did not answer.
+    result: Result<Vec<Enr>, Failure>,
+}
+
+/// A crawl in progress.
+struct Crawler {
+    local_node_id: NodeId,
+    nodes: Vec<CrawledNode>,
+    /// The index of each node in `nodes`.
+    index: HashMap<NodeId, usize>,
+    /// The discovered nodes that have not been crawled yet.
+    frontier: VecDeque<Enr>,
+}
+
+impl Crawler {
+    fn new(local_node_id: NodeId) -> Self {
+        Crawler {
+            local_node_id,
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            frontier: VecDeque::new(),
+        }
+    }
+
+    /// Records a node returned by a peer, or found in the local routing table if there is no
+    /// peer, and adds new nodes to the frontier.
+    fn discover(&mut self, enr: Enr, found_by: Option<NodeId>) {
+        let node_id = enr.node_id();
+        if node_id == self.local_node_id {
+            return;
+        }
+        let found_by = found_by.map(|peer| hex::encode(peer.raw()));
+        match self.index.get(&node_id) {
+            Some(&i) => {
+                let node = &mut self.nodes[i];
+                if let Some(peer) = found_by {
+                    if !node.found_by.contains(&peer) {
+                        node.found_by.push(peer);
+                    }
+                }
+                // Keep the latest record, which the node is crawled with if still in the frontier
+                if enr.seq() > node.enr.parse::<Enr>().map(|e| e.seq()).unwrap_or_default() {
+                    node.enr = enr.to_base64();
+                    if let Some(queued) = self.frontier.iter_mut().find(|e| e.node_id() == node_id)
+                    {
+                        *queued = enr;
+                    }
+                }
+            }
+            None => {
+                self.index.insert(node_id, self.nodes.len());
+                self.nodes.push(CrawledNode {
+                    node_id: hex::encode(node_id.raw()),
+                    enr: enr.to_base64(),
+                    discovered_at: now(),
+                    responsive: false,
+                    failure: None,
+                    requests: 0,
+                    returned: 0,
+                    found_by: found_by.into_iter().collect(),
+                });
+                self.frontier.push_back(enr);
+            }
+        }
+    }
+
+    /// Records the outcome of crawling a node.
+    fn visited(&mut self, node_id: NodeId, visit: Visit) {
+        let returned = match visit.result {
+            Ok(enrs) => {
+                let mut returned: Vec<NodeId> = enrs.iter().map(|enr| enr.node_id()).collect();
+                returned.sort_unstable_by_key(|id| id.raw());
+                returned.dedup();
+                for enr in enrs {
+                    self.discover(enr, Some(node_id));
+                }
+                Ok(returned.len())
+            }
+            Err(failure) => Err(failure),
+        };
+        if let Some(&i) = self.index.get(&node_id) {
+            let node = &mut self.nodes[i];
+            node.requests = visit.requests;
+            match returned {
+                Ok(returned) => {
+                    node.responsive = true;
+                    node.returned = returned;
+                }
+                Err(failure) => node.failure = Some(failure.kind().to_string()),
+            }
+        }
+    }
+}
+
+/// Crawls the network from the nodes of the local routing table until no new nodes are found, and
+/// returns every discovered node.
+///
+/// The FINDNODE requests are sent from `parallelism` short-lived nodes started from `local_node`
+/// rather than through the discv5 service, so that every NODES packet of a response is collected.
+/// The requests to each node are recorded as a query in the metrics if given.
+pub async fn crawl(
+    discv5: Arc<Discv5>,
+    local_node: &LocalNode,
+    settings: &Crawl,
+    metrics: Option<&Metrics>,
+) -> eyre::Result<CrawlDataset> {
+    let started_at = now();
+    let mut crawler = Crawler::new(discv5.local_enr().node_id());
+    for enr in discv5.table_entries_enr() {
+        crawler.discover(enr, None);
+    }
+    if crawler.frontier.is_empty() {
+        log::warn!("The routing table is empty, there are no nodes to start the crawl from");
+    }
+
+    let mut idle = start_nodes(local_node, settings.parallelism).await?;
+    let mut pending = FuturesUnordered::new();
+    let mut visited = 0;
+    loop {
+        while !idle.is_empty() {
+            let Some(enr) = crawler.frontier.pop_front() else {
+                break;
+            };
+            let mut node = idle.pop().expect("There is an idle node");
+            let settings = settings.clone();
+            pending.push(async move {
+                let node_id = enr.node_id();
+                let start = Instant::now();
+                let visit = visit(&mut node, enr, &settings).await;
+                (node, node_id, start.elapsed(), visit)
+            });
+        }
+        let Some((node, node_id, duration, visit)) = pending.next().await else {
+            break;
+        };
+        idle.push(node);
+        if let Some(metrics) = metrics {
+            metrics.record_query(QuerySource::Crawl, duration, &visit.result);
+        }
+        crawler.visited(node_id, visit);
+        visited += 1;
+        if visited % 100 == 0 {
+            log::info!(
+                "Crawled {} of {} discovered nodes, {} queued",
+                visited,
+                crawler.nodes.len(),
+                crawler.frontier.len()
+            );
+        }
+    }
+
+    Ok(CrawlDataset {
+        started_at,
+        finished_at: now(),
+        nodes: crawler.nodes,
+    })
+}
+
+/// Starts `count` short-lived nodes from `local_node` to send requests from. They listen on ipv6 as
+/// well where possible, so that nodes that only advertise an ipv6 socket can be contacted.
+pub(crate) async fn start_nodes(
+    local_node: &LocalNode,
+    count: usize,
+) -> eyre::Result<Vec<RawNode>> {
+    let mut nodes = Vec::new();
+    let dual_stack = match RawNode::start(local_node, true).await {
+        Ok(node) => {
+            nodes.push(node);
+            true
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to listen on ipv6, nodes only reachable over ipv6 are not contacted. Error: {}",
+                e
+            );
+            false
+        }
+    };
+    while nodes.len() < count.max(1) {
+        nodes.push(RawNode::start(local_node, dual_stack).await?);
+    }
+    Ok(nodes)
+}
+
+/// Requests the nodes of every bucket of a node, from the furthest distance down, until a number of
+/// consecutive distances return no nodes if `--empty-distances` is set.
+async fn visit(node: &mut RawNode, enr: Enr, settings: &Crawl) -> Visit {
+    let contact = match Target::Enr(enr.clone()).contact(node.ip_mode()) {
+        Ok(contact) => contact,
+        Err(e) => {
+            // A node with a socket of another address family is not malformed, just out of reach
+            let failure = if enr.udp4_socket().is_some() || enr.udp6_socket().is_some() {
+                Failure::Unreachable(e)
+            } else {
+                Failure::InvalidTarget(e)
+            };
+            return Visit {
+                requests: 0,
+                result: Err(failure),
+            };
+        }
+    };
+    let distances_per_request = settings.distances_per_request.max(1);
+    let mut requests = 0;
+    let mut found = Vec::new();
+    let mut answered = false;
+    let mut empty = 0;
+    let mut distance = MAX_DISTANCE;
+    while distance > 0 && (settings.empty_distances == 0 || empty < settings.empty_distances) {
+        let distances: Vec<u64> = (distance.saturating_sub(distances_per_request) + 1..=distance)
+            .rev()
+            .collect();
+        distance = distance.saturating_sub(distances_per_request);
+        requests += 1;
+        match findnode::request_nodes(node, contact.clone(), distances.clone()).await {
+            Ok(response) => {
+                if !response.is_complete() {
+                    log::debug!(
+                        "{} sent {} of {} NODES packets for distances {:?}",
+                        enr.node_id(),
+                        response.packets,
+                        response.total,
+                        distances
+                    );
+                }
+                let nodes = at_distances(&enr, response.enrs, &distances);
+                if nodes.is_empty() {
+                    empty += distances.len() as u64;
+                } else {
+                    empty = 0;
+                    found.extend(nodes);
+                }
+            }
+            Err(e) if !answered => {
+                log::debug!("{} did not answer. Error: {:?}", enr.node_id(), e);
+                return Visit {
+                    requests,
+                    result: Err(e.into()),
+                };
+            }
+            Err(e) => {
+                log::debug!(
+                    "{} stopped answering at distances {:?}. Error: {:?}",
+                    enr.node_id(),
+                    distances,
+                    e
+                );
+                break;
+            }
+        }
+        answered = true;
+    }
+    Visit {
+        requests,
+        result: Ok(found),
+    }
+}
+
+/// Drops the nodes a peer returned that are not at one of the requested distances from it, like
+/// the discv5 service does.
+pub(crate) fn at_distances(peer: &Enr, enrs: Vec<Enr>, distances: &[u64]) -> Vec<Enr> {
+    let peer_key = Key::from(peer.node_id());
+    enrs.into_iter()
+        .filter(|found| {
+            peer_key
+                .log2_distance(&Key::from(found.node_id()))
+                .is_some_and(|d| distances.contains(&d))
+        })
+        .collect()
+}
+
+/// Runs the crawl service and writes the discovered nodes as JSON, recording them for export if
+/// given.
+pub async fn run(
+    discv5: Arc<Discv5>,
+    local_node: &LocalNode,
+    crawl_cmd: &Crawl,
+    discoveries: Option<Arc<Discoveries>>,
+    metrics: Option<Arc<Metrics>>,
+) {
+    let dataset = match crawl(discv5, local_node, crawl_cmd, metrics.as_deref()).await {
+        Ok(dataset) => dataset,
+        Err(e) => {
+            log::error!("Failed to start the crawl. Error: {}", e);
+            return;
+        }
+    };
+    if let Some(discoveries) = discoveries {
+        discoveries.record_crawl(&dataset);
+    }
+    let responsive = dataset.nodes.iter().filter(|node| node.responsive).count();
+    log::info!(
+        "Crawl finished in {}s. Nodes discovered: {}, responsive: {}",
+        (dataset.finished_at - dataset.started_at) / 1000,
+        dataset.nodes.len(),
+        responsive
+    );
+
+    super::write_json(&dataset, crawl_cmd.output.as_deref(), "discovered nodes");
+}
+
+/// The current time in milliseconds since the unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -1,38 +1,48 @@
-use std::sync::Arc;
+use discv5::Event;
 
-use discv5::{Discv5, Event};
+use crate::server::event_bus::{BusEvent, EventReceiver};
 
 /// Streams the discv5 server event stream.
-pub async fn run(discv5: Arc<Discv5>) {
-    let mut event_stream = discv5.event_stream().await.unwrap();
-    loop {
-        match event_stream.recv().await {
-            Some(Event::SocketUpdated(addr)) => {
+pub async fn run(mut events: EventReceiver) {
+    while let Some(event) = events.recv().await {
+        let event = match &*event {
+            BusEvent::Discv5(event) => event,
+            BusEvent::TalkRequest { node_id, protocol } => {
+                log::info!(
+                    "A talk request for protocol 0x{} has been received from peer: {}",
+                    hex::encode(protocol),
+                    node_id
+                );
+                continue;
+            }
+        };
+        match event {
+            Event::SocketUpdated(addr) => {
                 log::info!("Nodes ENR socket address has been updated to: {:?}", addr);
             }
-            Some(Event::Discovered(enr)) => {
+            Event::Discovered(enr) => {
                 log::info!("A peer has been discovered: {}", enr.node_id());
             }
-            Some(Event::UnverifiableEnr { enr, .. }) => {
+            Event::UnverifiableEnr { enr, .. } => {
                 log::info!(
                     "A peer has been added to the routing table with enr: {}",
                     enr
                 );
             }
-            Some(Event::NodeInserted { node_id, .. }) => {
+            Event::NodeInserted { node_id, .. } => {
                 log::info!(
                     "A peer has been added to the routing table with node_id: {}",
                     node_id
                 );
             }
-            Some(Event::SessionEstablished(enr, addr)) => {
+            Event::SessionEstablished(enr, addr) => {
                 log::info!(
                     "A session has been established with peer: {} at address: {}",
                     enr,
                     addr
                 );
             }
-            Some(Event::TalkRequest(talk_request)) => {
+            Event::TalkRequest(talk_request) => {
                 log::info!(
                     "A talk request has been received from peer: {}",
                     talk_request.node_id()
@@ -0,0 +1,510 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use discv5::{
+    enr::{self, NodeId},
+    Discv5, Event,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    crawl::{self, CrawlDataset},
+    graph,
+    query::PeerLookup,
+};
+use crate::{
+    request_enr::output::{EnrDetails, Eth2Details},
+    server::{
+        bootstrap::{BootstrapNode, BootstrapStore, Direction, State},
+        data_dir,
+        event_bus::EventReceiver,
+    },
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The columns of a CSV export.
+const CSV_HEADER: [&str; 20] = [
+    "node_id",
+    "peer_id",
+    "seq",
+    "ip4",
+    "udp4",
+    "tcp4",
+    "ip6",
+    "udp6",
+    "tcp6",
+    "fork_digest",
+    "next_fork_version",
+    "next_fork_epoch",
+    "source",
+    "first_seen",
+    "last_seen",
+    "state",
+    "direction",
+    "responsive",
+    "found_by",
+    "enr",
+];
+
+/// The format of an export file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ExportFormat {
+    /// A row of decoded fields per node.
+    Csv,
+    /// A JSON object of decoded fields per line.
+    Jsonl,
+    /// A [`BootstrapStore`] to bootstrap a server from with `--bootstrap`.
+    Bootstrap,
+    /// The peer graph in the Graphviz DOT language.
+    Dot,
+    /// The peer graph in GraphML.
+    GraphMl,
+}
+
+impl ExportFormat {
+    /// The format of a file based on its extension: `.csv`, `.jsonl`, `.json` for a bootstrap
+    /// file, `.dot` or `.graphml`.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Ok(ExportFormat::Csv),
+            Some("jsonl") | Some("ndjson") => Ok(ExportFormat::Jsonl),
+            Some("json") => Ok(ExportFormat::Bootstrap),
+            Some("dot") | Some("gv") => Ok(ExportFormat::Dot),
+            Some("graphml") => Ok(ExportFormat::GraphMl),
+            _ => Err(format!(
+                "Unknown export format of {}, expected a .csv, .jsonl, .json, .dot or .graphml file",
+                path.display()
+            )),
+        }
+    }
+
+    /// Whether the format is a peer graph, whose edges come from crawls and the lookups of the
+    /// query service.
+    pub fn is_peer_graph(self) -> bool {
+        matches!(self, ExportFormat::Dot | ExportFormat::GraphMl)
+    }
+
+    /// Parses an `--export` file, rejecting files whose format is unknown.
+    pub fn parse_path(path: &str) -> Result<String, String> {
+        ExportFormat::from_path(Path::new(path)).map(|_| path.to_string())
+    }
+}
+
+/// How a node was first discovered.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// Returned by a query.
+    Query,
+    /// Established a session with the server.
+    Session,
+    /// Found in the routing table.
+    Table,
+    /// Found by a crawl.
+    Crawl,
+}
+
+impl Source {
+    fn as_str(self) -> &'static str {
+        match self {
+            Source::Query => "query",
+            Source::Session => "session",
+            Source::Table => "table",
+            Source::Crawl => "crawl",
+        }
+    }
+}
+
+/// A discovered node with the decoded fields of its ENR.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiscoveredNode {
+    /// The hex encoded node id.
+    pub node_id: String,
+    /// The libp2p peer id of the node's key.
+    pub peer_id: String,
+    /// The sequence number of the ENR.
+    pub seq: u64,
+    /// The ipv4 address.
+    pub ip4: Option<std::net::Ipv4Addr>,
+    /// The ipv4 UDP port.
+    pub udp4: Option<u16>,
+    /// The ipv4 TCP port.
+    pub tcp4: Option<u16>,
+    /// The ipv6 address.
+    pub ip6: Option<std::net::Ipv6Addr>,
+    /// The ipv6 UDP port.
+    pub udp6: Option<u16>,
+    /// The ipv6 TCP port.
+    pub tcp6: Option<u16>,
+    /// The hex encoded fork digest of the `eth2` field.
+    pub fork_digest: Option<String>,
+    /// The hex encoded next fork version of the `eth2` field.
+    pub next_fork_version: Option<String>,
+    /// The next fork epoch of the `eth2` field.
+    pub next_fork_epoch: Option<u64>,
+    /// How the node was first discovered.
+    pub source: Source,
+    /// When the node was first discovered, in milliseconds since the unix epoch.
+    pub first_seen: u64,
+    /// When the node was last discovered, in milliseconds since the unix epoch.
+    pub last_seen: u64,
+    /// The connection state if the node is in the routing table.
+    pub state: Option<State>,
+    /// The connection direction if the node is in the routing table.
+    pub direction: Option<Direction>,
+    /// Whether the node answered a crawl or a lookup of the query service, if it was contacted.
+    pub responsive: Option<bool>,
+    /// The hex encoded node ids of the nodes that returned this node to a crawl or a lookup of the
+    /// query service.
+    pub found_by: Vec<String>,
+    /// The base64 encoded ENR with the highest sequence number seen.
+    pub enr: String,
+}
+
+impl DiscoveredNode {
+    fn new(enr: &Enr, source: Source, seen: u64) -> Self {
+        let mut node = DiscoveredNode {
+            node_id: String::new(),
+            peer_id: String::new(),
+            seq: 0,
+            ip4: None,
+            udp4: None,
+            tcp4: None,
+            ip6: None,
+            udp6: None,
+            tcp6: None,
+            fork_digest: None,
+            next_fork_version: None,
+            next_fork_epoch: None,
+            source,
+            first_seen: seen,
+            last_seen: seen,
+            state: None,
+            direction: None,
+            responsive: None,
+            found_by: Vec::new(),
+            enr: String::new(),
+        };
+        node.set_enr(enr);
+        node
+    }
+
+    /// Sets the ENR and its decoded fields.
+    fn set_enr(&mut self, enr: &Enr) {
+        let details = EnrDetails::from(enr);
+        let (fork_digest, next_fork_version, next_fork_epoch) = match details.eth2 {
+            Some(Eth2Details::ForkId {
+                fork_digest,
+                next_fork_version,
+                next_fork_epoch,
+            }) => (
+                Some(fork_digest),
+                Some(next_fork_version),
+                Some(next_fork_epoch),
+            ),
+            _ => (None, None, None),
+        };
+        self.node_id = details.node_id;
+        self.peer_id = details.peer_id;
+        self.seq = details.seq;
+        self.ip4 = details.ip4;
+        self.udp4 = details.udp4;
+        self.tcp4 = details.tcp4;
+        self.ip6 = details.ip6;
+        self.udp6 = details.udp6;
+        self.tcp6 = details.tcp6;
+        self.fork_digest = fork_digest;
+        self.next_fork_version = next_fork_version;
+        self.next_fork_epoch = next_fork_epoch;
+        self.enr = details.enr;
+    }
+
+    /// The fields of the node in the order of [`CSV_HEADER`].
+    fn csv_record(&self) -> Vec<String> {
+        fn field<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(ToString::to_string).unwrap_or_default()
+        }
+        vec![
+            self.node_id.clone(),
+            self.peer_id.clone(),
+            self.seq.to_string(),
+            field(&self.ip4),
+            field(&self.udp4),
+            field(&self.tcp4),
+            field(&self.ip6),
+            field(&self.udp6),
+            field(&self.tcp6),
+            field(&self.fork_digest),
+            field(&self.next_fork_version),
+            field(&self.next_fork_epoch),
+            self.source.as_str().to_string(),
+            self.first_seen.to_string(),
+            self.last_seen.to_string(),
+            match self.state {
+                Some(State::Connected) => "connected".to_string(),
+                Some(State::Disconnected) => "disconnected".to_string(),
+                None => String::new(),
+            },
+            match self.direction {
+                Some(Direction::Inbound) => "inbound".to_string(),
+                Some(Direction::Outbound) => "outbound".to_string(),
+                None => String::new(),
+            },
+            field(&self.responsive),
+            self.found_by.join(";"),
+            self.enr.clone(),
+        ]
+    }
+}
+
+/// Collects the nodes the server discovers, up to a maximum number of nodes.
+#[derive(Debug)]
+pub struct Discoveries {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The nodes in the order they were discovered.
+    nodes: Vec<DiscoveredNode>,
+    /// The index of each node in `nodes`.
+    index: HashMap<NodeId, usize>,
+    /// The number of nodes kept.
+    max_nodes: usize,
+}
+
+impl Inner {
+    /// Records a sighting of a node, keeping the ENR with the highest sequence number.
+    fn record(&mut self, enr: &Enr, source: Source, seen: u64) -> &mut DiscoveredNode {
+        let i = match self.index.get(&enr.node_id()) {
+            Some(&i) => {
+                let node = &mut self.nodes[i];
+                node.first_seen = node.first_seen.min(seen);
+                node.last_seen = node.last_seen.max(seen);
+                if enr.seq() > node.seq {
+                    node.set_enr(enr);
+                }
+                i
+            }
+            None => {
+                self.index.insert(enr.node_id(), self.nodes.len());
+                self.nodes.push(DiscoveredNode::new(enr, source, seen));
+                self.nodes.len() - 1
+            }
+        };
+        &mut self.nodes[i]
+    }
+
+    /// Drops the least recently seen nodes once there are more than `max_nodes`. A tenth of the
+    /// nodes are dropped at once, so that the nodes are not scanned for every new node.
+    fn evict(&mut self) {
+        if self.nodes.len() <= self.max_nodes {
+            return;
+        }
+        let keep = self.max_nodes - self.max_nodes / 10;
+        let mut last_seen: Vec<u64> = self.nodes.iter().map(|node| node.last_seen).collect();
+        let (_, &mut cutoff, _) = last_seen.select_nth_unstable_by(keep, |a, b| b.cmp(a));
+        // Keep the nodes seen after the cutoff, and as many seen at the cutoff as fit
+        let mut at_cutoff = keep
+            - self
+                .nodes
+                .iter()
+                .filter(|node| node.last_seen > cutoff)
+                .count();
+        let mut position = vec![None; self.nodes.len()];
+        let mut kept = 0;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let at_cutoff_kept = node.last_seen == cutoff && at_cutoff > 0;
+            if node.last_seen > cutoff || at_cutoff_kept {
+                at_cutoff -= usize::from(at_cutoff_kept);
+                position[i] = Some(kept);
+                kept += 1;
+            }
+        }
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .zip(&position)
+            .filter_map(|(node, position)| position.map(|_| node))
+            .collect();
+        self.index.retain(|_, i| match position[*i] {
+            Some(position) => {
+                *i = position;
+                true
+            }
+            None => false,
+        });
+        log::debug!(
+            "Dropped the least recently seen of {} discovered nodes, keeping {}",
+            position.len(),
+            self.nodes.len()
+        );
+    }
+}
+
+impl Discoveries {
+    /// Collects up to `max_nodes` nodes, dropping the least recently seen nodes beyond that.
+    pub fn new(max_nodes: usize) -> Self {
+        Discoveries {
+            inner: Mutex::new(Inner {
+                max_nodes: max_nodes.max(1),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Records a node discovered now.
+    pub fn record(&self, enr: &Enr, source: Source) {
+        let mut inner = self.inner.lock();
+        inner.record(enr, source, crawl::now());
+        inner.evict();
+    }
+
+    /// Records the nodes of a crawl with their responsiveness and the peers that returned them.
+    pub fn record_crawl(&self, dataset: &CrawlDataset) {
+        let mut inner = self.inner.lock();
+        for crawled in &dataset.nodes {
+            let Ok(enr) = crawled.enr.parse::<Enr>() else {
+                continue;
+            };
+            let node = inner.record(&enr, Source::Crawl, crawled.discovered_at);
+            node.responsive = Some(node.responsive.unwrap_or_default() || crawled.responsive);
+            for peer in &crawled.found_by {
+                if !node.found_by.contains(peer) {
+                    node.found_by.push(peer.clone());
+                }
+            }
+        }
+        inner.evict();
+    }
+
+    /// Records the nodes of a lookup from short-lived nodes with the peers that returned them and
+    /// whether the contacted peers answered.
+    pub fn record_lookup(&self, lookup: &PeerLookup) {
+        let seen = crawl::now();
+        let mut inner = self.inner.lock();
+        for (peer, returned) in &lookup.responses {
+            inner.record(peer, Source::Query, seen).responsive = Some(true);
+            let peer = hex::encode(peer.node_id().raw());
+            for enr in returned {
+                let node = inner.record(enr, Source::Query, seen);
+                if !node.found_by.contains(&peer) {
+                    node.found_by.push(peer.clone());
+                }
+            }
+        }
+        for peer in &lookup.unresponsive {
+            inner
+                .record(peer, Source::Query, seen)
+                .responsive
+                .get_or_insert(false);
+        }
+        inner.evict();
+    }
+
+    /// Records the nodes of the routing table and updates the connection state of every node.
+    pub fn record_table(&self, discv5: &Discv5) {
+        let seen = crawl::now();
+        let mut inner = self.inner.lock();
+        for node in &mut inner.nodes {
+            node.state = None;
+            node.direction = None;
+        }
+        for (_, enr, status) in discv5.table_entries() {
+            let node = inner.record(&enr, Source::Table, seen);
+            node.state = Some(status.state.into());
+            node.direction = Some(status.direction.into());
+        }
+        inner.evict();
+    }
+
+    /// The discovered nodes, in the order they were discovered.
+    pub fn nodes(&self) -> Vec<DiscoveredNode> {
+        self.inner.lock().nodes.clone()
+    }
+}
+
+/// Whether any of the export files is a peer graph.
+pub fn exports_peer_graph(paths: &[String]) -> bool {
+    paths
+        .iter()
+        .any(|path| ExportFormat::from_path(Path::new(path)).is_ok_and(ExportFormat::is_peer_graph))
+}
+
+/// Writes the nodes to a file. The file is replaced atomically, so readers never see a partial
+/// export.
+pub fn write(path: &Path, format: ExportFormat, nodes: &[DiscoveredNode]) -> eyre::Result<()> {
+    let contents = match format {
+        ExportFormat::Csv => {
+            let mut csv = csv_line(CSV_HEADER.iter().map(|column| column.to_string()));
+            for node in nodes {
+                csv.push_str(&csv_line(node.csv_record()));
+            }
+            csv
+        }
+        ExportFormat::Jsonl => {
+            let mut jsonl = String::new();
+            for node in nodes {
+                jsonl.push_str(&serde_json::to_string(node)?);
+                jsonl.push('\n');
+            }
+            jsonl
+        }
+        ExportFormat::Bootstrap => serde_json::to_string_pretty(&bootstrap_store(nodes))?,
+        ExportFormat::Dot => graph::to_dot(nodes),
+        ExportFormat::GraphMl => graph::to_graphml(nodes),
+    };
+    data_dir::write_atomic(path, contents.as_bytes(), false)
+}
+
+/// The nodes as a bootstrap file, leaving out crawled nodes that did not answer and listing
+/// connected nodes first.
+pub fn bootstrap_store(nodes: &[DiscoveredNode]) -> BootstrapStore {
+    let mut data: Vec<BootstrapNode> = nodes
+        .iter()
+        .filter(|node| node.responsive != Some(false))
+        .filter_map(|node| {
+            let enr = node.enr.parse::<Enr>().ok()?;
+            Some(BootstrapNode::new(
+                &enr,
+                node.state.clone().unwrap_or(State::Disconnected),
+                node.direction.clone().unwrap_or(Direction::Outbound),
+            ))
+        })
+        .collect();
+    data.sort_by_key(|node| node.state != State::Connected);
+    BootstrapStore { data }
+}
+
+/// Records the nodes of the event stream.
+pub fn run(discoveries: Arc<Discoveries>, mut events: EventReceiver) {
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            match event.discv5() {
+                Some(Event::Discovered(enr)) => discoveries.record(enr, Source::Query),
+                Some(Event::SessionEstablished(enr, _) | Event::UnverifiableEnr { enr, .. }) => {
+                    discoveries.record(enr, Source::Session)
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Joins the fields into a CSV line, quoting fields that contain separators or quotes.
+fn csv_line(fields: impl IntoIterator<Item = String>) -> String {
+    let mut line = fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
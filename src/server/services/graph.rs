@@ -0,0 +1,124 @@
+use std::{collections::HashSet, fmt::Write};
+
+use super::export::DiscoveredNode;
+
+/// The number of hex characters of a node id used as its label.
+const LABEL_LENGTH: usize = 8;
+
+/// The annotations of a node in the peer graph.
+struct Annotations<'a> {
+    fork_digest: &'a str,
+    ip_family: &'static str,
+    responsiveness: &'static str,
+}
+
+impl<'a> From<&'a DiscoveredNode> for Annotations<'a> {
+    fn from(node: &'a DiscoveredNode) -> Self {
+        Annotations {
+            fork_digest: node.fork_digest.as_deref().unwrap_or_default(),
+            ip_family: match (node.ip4.is_some(), node.ip6.is_some()) {
+                (true, true) => "dual",
+                (true, false) => "ipv4",
+                (false, true) => "ipv6",
+                (false, false) => "none",
+            },
+            responsiveness: match node.responsive {
+                Some(true) => "responsive",
+                Some(false) => "unresponsive",
+                None => "unknown",
+            },
+        }
+    }
+}
+
+/// The edges of the graph, from each node to the nodes it returned to a crawl or a lookup of the
+/// query service.
+fn edges(nodes: &[DiscoveredNode]) -> Vec<(&str, &str)> {
+    let known: HashSet<&str> = nodes.iter().map(|node| node.node_id.as_str()).collect();
+    nodes
+        .iter()
+        .flat_map(|node| {
+            node.found_by
+                .iter()
+                .filter(|peer| known.contains(peer.as_str()))
+                .map(move |peer| (peer.as_str(), node.node_id.as_str()))
+        })
+        .collect()
+}
+
+/// Renders the nodes and who returned whom as a directed graph in the Graphviz DOT language.
+///
+/// Unresponsive nodes are dashed and nodes that were not contacted are dotted.
+pub fn to_dot(nodes: &[DiscoveredNode]) -> String {
+    let mut dot = String::from("digraph discv5 {\n");
+    for node in nodes {
+        let annotations = Annotations::from(node);
+        let style = match annotations.responsiveness {
+            "responsive" => "solid",
+            "unresponsive" => "dashed",
+            _ => "dotted",
+        };
+        let _ = writeln!(
+            dot,
+            "  \"{}\" [label=\"{}\", fork_digest=\"{}\", ip_family=\"{}\", responsiveness=\"{}\", style={}];",
+            node.node_id,
+            &node.node_id[..LABEL_LENGTH.min(node.node_id.len())],
+            annotations.fork_digest,
+            annotations.ip_family,
+            annotations.responsiveness,
+            style
+        );
+    }
+    for (from, to) in edges(nodes) {
+        let _ = writeln!(dot, "  \"{from}\" -> \"{to}\";");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders the nodes and who returned whom as a directed graph in GraphML.
+pub fn to_graphml(nodes: &[DiscoveredNode]) -> String {
+    let mut graphml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+    );
+    for key in ["fork_digest", "ip_family", "responsiveness", "enr"] {
+        let _ = writeln!(
+            graphml,
+            "  <key id=\"{key}\" for=\"node\" attr.name=\"{key}\" attr.type=\"string\"/>"
+        );
+    }
+    graphml.push_str("  <graph id=\"discv5\" edgedefault=\"directed\">\n");
+    for node in nodes {
+        let annotations = Annotations::from(node);
+        let _ = writeln!(graphml, "    <node id=\"{}\">", node.node_id);
+        for (key, value) in [
+            ("fork_digest", annotations.fork_digest),
+            ("ip_family", annotations.ip_family),
+            ("responsiveness", annotations.responsiveness),
+            ("enr", node.enr.as_str()),
+        ] {
+            let _ = writeln!(
+                graphml,
+                "      <data key=\"{}\">{}</data>",
+                key,
+                escape_xml(value)
+            );
+        }
+        graphml.push_str("    </node>\n");
+    }
+    for (from, to) in edges(nodes) {
+        let _ = writeln!(graphml, "    <edge source=\"{from}\" target=\"{to}\"/>");
+    }
+    graphml.push_str("  </graph>\n</graphml>\n");
+    graphml
+}
+
+/// Escapes the characters of a string that are not allowed in XML text.
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,389 @@
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc, time::Instant};
+
+use axum::{
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use discv5::{
+    enr::{self, CombinedKey, CombinedPublicKey, NodeId},
+    kbucket::NodeStatus,
+    ConnectionDirection, ConnectionState, Discv5, Key,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    metrics::{Metrics, QuerySource},
+    query,
+};
+use crate::{
+    request_enr::{output::EnrDetails, target::Target},
+    server::fork_digest::ForkDigestFilter,
+    talk,
+    utils::node_id,
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The result of an API call.
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// A failed API call, returned as `{"error": <message>}`.
+#[derive(Debug)]
+pub struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(message: impl ToString) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, message.to_string())
+    }
+
+    fn not_found(message: impl ToString) -> Self {
+        ApiError(StatusCode::NOT_FOUND, message.to_string())
+    }
+
+    fn bad_gateway(message: impl ToString) -> Self {
+        ApiError(StatusCode::BAD_GATEWAY, message.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// A routing table entry.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TableEntry {
+    /// The hex encoded node id.
+    pub node_id: String,
+    /// The base64 encoded ENR.
+    pub enr: String,
+    /// The log2 distance to the local node.
+    pub distance: Option<u64>,
+    /// Whether the node is `connected` or `disconnected`.
+    pub state: String,
+    /// Whether the node contacted us (`incoming`) or we contacted it (`outgoing`).
+    pub direction: String,
+}
+
+/// The number of known nodes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Peers {
+    /// The number of connected nodes in the routing table.
+    pub connected: usize,
+    /// The number of nodes in the routing table.
+    pub table: usize,
+}
+
+/// A request for an ENR to be added to the routing table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddEnr {
+    /// The base64 encoded ENR.
+    pub enr: String,
+}
+
+/// A request naming a node.
+///
+/// Nodes can be given as a request-enr target (ENR, multiaddr, enode URL or
+/// `<public-key>@<ip>:<port>`) or by the node id of a routing table entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeRequest {
+    /// The node.
+    pub node: String,
+}
+
+/// A request for a lookup of the nodes closest to a target.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FindNodeRequest {
+    /// The node id to look up. A random node id is used if not given.
+    pub target: Option<String>,
+}
+
+/// A PONG response.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PongResponse {
+    /// The ENR sequence number of the node.
+    pub enr_seq: u64,
+    /// Our IP address as observed by the node.
+    pub ip: std::net::IpAddr,
+    /// Our UDP port as observed by the node.
+    pub port: u16,
+    /// The round-trip time of the request.
+    pub latency_ms: u128,
+}
+
+/// A TALKREQ to send to a node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TalkRequestBody {
+    /// The node.
+    pub node: String,
+    /// The protocol, as utf8 or 0x prefixed hex.
+    pub protocol: String,
+    /// The hex encoded request payload.
+    #[serde(default)]
+    pub request: String,
+}
+
+/// A TALKRESP received from a node.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TalkResponseBody {
+    /// The hex encoded response payload.
+    pub response: String,
+}
+
+/// Serves the routes on the address, such as the [`router`] of the HTTP API.
+pub async fn serve(addr: SocketAddr, routes: Router) -> eyre::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    log::info!("HTTP server listening on {}", listener.local_addr()?);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, routes).await {
+            log::error!("The HTTP server stopped. Error: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// The state of the HTTP API.
+#[derive(Clone)]
+struct ApiState {
+    discv5: Arc<Discv5>,
+    fork_digest_filter: Option<Arc<ForkDigestFilter>>,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl FromRef<ApiState> for Arc<Discv5> {
+    fn from_ref(state: &ApiState) -> Self {
+        Arc::clone(&state.discv5)
+    }
+}
+
+/// The routes of the HTTP API. With a fork digest filter, lookups only return nodes of its
+/// network, and they are recorded in the metrics if given.
+pub fn router(
+    discv5: Arc<Discv5>,
+    fork_digest_filter: Option<Arc<ForkDigestFilter>>,
+    metrics: Option<Arc<Metrics>>,
+) -> Router {
+    Router::new()
+        .route("/enr", get(local_enr).patch(update_enr))
+        .route("/table", get(table_entries).post(add_enr))
+        .route("/table/{node_id}", delete(remove_node))
+        .route("/peers", get(peers))
+        .route("/find-node", post(find_node))
+        .route("/request-enr", post(request_enr))
+        .route("/ping", post(ping))
+        .route("/talk", post(talk_req))
+        .with_state(ApiState {
+            discv5,
+            fork_digest_filter,
+            metrics,
+        })
+}
+
+/// `GET /enr`: the local ENR.
+async fn local_enr(State(discv5): State<Arc<Discv5>>) -> Json<EnrDetails> {
+    Json(EnrDetails::from(&discv5.local_enr()))
+}
+
+/// `PATCH /enr`: sets the fields of the local ENR given as an object of keys to hex values.
+///
+/// The fields are first set on a copy of the ENR, so the local ENR is only updated if all of them
+/// are valid.
+async fn update_enr(
+    State(discv5): State<Arc<Discv5>>,
+    Json(fields): Json<BTreeMap<String, String>>,
+) -> ApiResult<EnrDetails> {
+    let mut values = Vec::with_capacity(fields.len());
+    for (key, value) in fields {
+        let value = hex::decode(value.trim_start_matches("0x"))
+            .map_err(|_| ApiError::bad_request(format!("Invalid hex value for {key}")))?;
+        values.push((key, value));
+    }
+
+    // Keys of the same type sign records of the same size
+    let mut copy = discv5.local_enr();
+    let copy_key = match copy.public_key() {
+        CombinedPublicKey::Secp256k1(_) => CombinedKey::generate_secp256k1(),
+        CombinedPublicKey::Ed25519(_) => CombinedKey::generate_ed25519(),
+    };
+    for (key, value) in &values {
+        copy.insert(key, &value.as_slice(), &copy_key)
+            .map_err(|e| ApiError::bad_request(format!("Failed to set {key}: {e:?}")))?;
+    }
+
+    for (key, value) in values {
+        discv5
+            .enr_insert(&key, &value.as_slice())
+            .map_err(|e| ApiError::bad_request(format!("Failed to set {key}: {e:?}")))?;
+        log::info!("Set the ENR field {} over the HTTP API", key);
+    }
+    Ok(Json(EnrDetails::from(&discv5.local_enr())))
+}
+
+/// `GET /table`: the entries of the routing table.
+async fn table_entries(State(discv5): State<Arc<Discv5>>) -> Json<Vec<TableEntry>> {
+    let local_key = Key::from(discv5.local_enr().node_id());
+    let entries = discv5
+        .table_entries()
+        .into_iter()
+        .map(|(node_id, enr, status)| table_entry(&local_key, node_id, &enr, status))
+        .collect();
+    Json(entries)
+}
+
+/// Describes a routing table entry.
+fn table_entry(
+    local_key: &Key<NodeId>,
+    node_id: NodeId,
+    enr: &Enr,
+    status: NodeStatus,
+) -> TableEntry {
+    TableEntry {
+        node_id: hex::encode(node_id.raw()),
+        enr: enr.to_base64(),
+        distance: local_key.log2_distance(&Key::from(node_id)),
+        state: match status.state {
+            ConnectionState::Connected => "connected",
+            ConnectionState::Disconnected => "disconnected",
+        }
+        .to_string(),
+        direction: match status.direction {
+            ConnectionDirection::Incoming => "incoming",
+            ConnectionDirection::Outgoing => "outgoing",
+        }
+        .to_string(),
+    }
+}
+
+/// `POST /table`: adds an ENR to the routing table.
+async fn add_enr(
+    State(discv5): State<Arc<Discv5>>,
+    Json(request): Json<AddEnr>,
+) -> ApiResult<TableEntry> {
+    let enr = request
+        .enr
+        .trim()
+        .parse::<Enr>()
+        .map_err(ApiError::bad_request)?;
+    let node_id = enr.node_id();
+    discv5.add_enr(enr).map_err(ApiError::bad_request)?;
+    let local_key = Key::from(discv5.local_enr().node_id());
+    discv5
+        .table_entries()
+        .into_iter()
+        .find(|(id, _, _)| *id == node_id)
+        .map(|(node_id, enr, status)| Json(table_entry(&local_key, node_id, &enr, status)))
+        .ok_or_else(|| ApiError::not_found(format!("{node_id} was not added to the routing table")))
+}
+
+/// `DELETE /table/{node_id}`: removes a node from the routing table.
+async fn remove_node(
+    State(discv5): State<Arc<Discv5>>,
+    Path(node): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let node_id = node_id::parse(&node).map_err(ApiError::bad_request)?;
+    if discv5.remove_node(&node_id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found(format!(
+            "{node_id} is not in the routing table"
+        )))
+    }
+}
+
+/// `GET /peers`: the number of known nodes.
+async fn peers(State(discv5): State<Arc<Discv5>>) -> Json<Peers> {
+    Json(Peers {
+        connected: discv5.connected_peers(),
+        table: discv5.table_entries_id().len(),
+    })
+}
+
+/// `POST /find-node`: looks up the nodes closest to a target.
+async fn find_node(
+    State(state): State<ApiState>,
+    Json(request): Json<FindNodeRequest>,
+) -> ApiResult<Vec<EnrDetails>> {
+    let target = match request.target {
+        Some(target) => node_id::parse(&target).map_err(ApiError::bad_request)?,
+        None => NodeId::random(),
+    };
+    let start = Instant::now();
+    let result = query::lookup(&state.discv5, target, state.fork_digest_filter.as_ref()).await;
+    if let Some(metrics) = &state.metrics {
+        metrics.record_query(QuerySource::Http, start.elapsed(), &result);
+    }
+    let enrs = result.map_err(|e| ApiError::bad_gateway(format!("{e:?}")))?;
+    Ok(Json(enrs.iter().map(EnrDetails::from).collect()))
+}
+
+/// `POST /request-enr`: requests the latest ENR of a node.
+async fn request_enr(
+    State(discv5): State<Arc<Discv5>>,
+    Json(request): Json<NodeRequest>,
+) -> ApiResult<EnrDetails> {
+    let target = request
+        .node
+        .parse::<Target>()
+        .map_err(ApiError::bad_request)?;
+    let enr = target
+        .request_verified(&discv5)
+        .await
+        .map_err(ApiError::bad_gateway)?;
+    Ok(Json(EnrDetails::from(&enr)))
+}
+
+/// `POST /ping`: pings a node.
+async fn ping(
+    State(discv5): State<Arc<Discv5>>,
+    Json(request): Json<NodeRequest>,
+) -> ApiResult<PongResponse> {
+    let enr = resolve(&discv5, &request.node).await?;
+    let start = Instant::now();
+    let pong = discv5
+        .send_ping(enr)
+        .await
+        .map_err(|e| ApiError::bad_gateway(format!("{e:?}")))?;
+    Ok(Json(PongResponse {
+        enr_seq: pong.enr_seq,
+        ip: pong.ip,
+        port: pong.port,
+        latency_ms: start.elapsed().as_millis(),
+    }))
+}
+
+/// `POST /talk`: sends a TALKREQ to a node.
+async fn talk_req(
+    State(discv5): State<Arc<Discv5>>,
+    Json(request): Json<TalkRequestBody>,
+) -> ApiResult<TalkResponseBody> {
+    let protocol = talk::parse_protocol(&request.protocol).map_err(ApiError::bad_request)?;
+    let payload = talk::parse_payload(&request.request, talk::PayloadEncoding::Hex)
+        .map_err(ApiError::bad_request)?;
+    let enr = resolve(&discv5, &request.node).await?;
+    let response = discv5
+        .talk_req(enr, protocol, payload)
+        .await
+        .map_err(|e| ApiError::bad_gateway(format!("{e:?}")))?;
+    Ok(Json(TalkResponseBody {
+        response: format!("0x{}", hex::encode(response)),
+    }))
+}
+
+/// Finds the ENR of a node given as a request-enr target or by the node id of a routing table
+/// entry.
+async fn resolve(discv5: &Discv5, node: &str) -> Result<Enr, ApiError> {
+    if let Ok(target) = node.parse::<Target>() {
+        return target
+            .resolve(discv5)
+            .await
+            .map_err(|e| ApiError::bad_gateway(format!("{e:?}")));
+    }
+    let node_id = node_id::parse(node).map_err(ApiError::bad_request)?;
+    discv5
+        .find_enr(&node_id)
+        .ok_or_else(|| ApiError::not_found(format!("{node_id} is not in the routing table")))
+}
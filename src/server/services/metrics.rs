@@ -0,0 +1,348 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use discv5::{enr, Discv5, Event};
+use prometheus_client::{
+    collector::Collector,
+    encoding::{DescriptorEncoder, EncodeLabelSet, EncodeMetric},
+    metrics::{
+        counter::{ConstCounter, Counter},
+        family::Family,
+        gauge::ConstGauge,
+        histogram::{exponential_buckets, Histogram},
+        MetricType,
+    },
+    registry::Registry,
+};
+
+use super::stats::{self, BucketStatistic};
+use crate::server::{
+    event_bus::{BusEvent, EventReceiver},
+    fork_digest::ForkDigestFilter,
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The content type of the OpenMetrics text format.
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// The address family of a session.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct FamilyLabels {
+    family: &'static str,
+}
+
+/// The type of a discv5 event.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct EventLabels {
+    r#type: &'static str,
+}
+
+/// The service that ran a query.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct SourceLabels {
+    source: &'static str,
+}
+
+/// The service that ran a query and whether it succeeded.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ResultLabels {
+    source: &'static str,
+    result: &'static str,
+}
+
+/// The service that ran a query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuerySource {
+    /// A lookup of the query service.
+    Query,
+    /// A lookup of the subnet search service.
+    SubnetSearch,
+    /// A lookup of the HTTP API.
+    Http,
+    /// The FINDNODE requests of a crawl to a single node.
+    Crawl,
+}
+
+impl QuerySource {
+    fn as_str(self) -> &'static str {
+        match self {
+            QuerySource::Query => "query",
+            QuerySource::SubnetSearch => "subnet_search",
+            QuerySource::Http => "http",
+            QuerySource::Crawl => "crawl",
+        }
+    }
+}
+
+/// The Prometheus metrics of the server.
+pub struct Metrics {
+    registry: Registry,
+    sessions: Family<FamilyLabels, Counter>,
+    events: Family<EventLabels, Counter>,
+    queries: Family<ResultLabels, Counter>,
+    query_duration: Family<SourceLabels, Histogram>,
+    query_nodes: Family<SourceLabels, Histogram>,
+}
+
+impl Metrics {
+    /// Creates the metrics of a discv5 server. The routing table and the metrics of discv5 itself
+    /// are read on every scrape.
+    pub fn new(discv5: Arc<Discv5>) -> Self {
+        let mut registry = Registry::with_prefix("discv5");
+        let sessions = Family::<FamilyLabels, Counter>::default();
+        registry.register(
+            "sessions_established",
+            "Sessions established per address family",
+            sessions.clone(),
+        );
+        let events = Family::<EventLabels, Counter>::default();
+        registry.register("events", "Discv5 events by type", events.clone());
+        let queries = Family::<ResultLabels, Counter>::default();
+        registry.register(
+            "queries",
+            "Completed queries by service and result",
+            queries.clone(),
+        );
+        let query_duration = Family::<SourceLabels, Histogram>::new_with_constructor(|| {
+            Histogram::new(exponential_buckets(0.25, 2.0, 10))
+        });
+        registry.register(
+            "query_duration_seconds",
+            "The duration of queries by service",
+            query_duration.clone(),
+        );
+        let query_nodes = Family::<SourceLabels, Histogram>::new_with_constructor(|| {
+            Histogram::new([0.0, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0])
+        });
+        registry.register(
+            "query_found_nodes",
+            "The number of nodes found by successful queries by service",
+            query_nodes.clone(),
+        );
+        registry.register_collector(Box::new(Discv5Collector { discv5 }));
+
+        Metrics {
+            registry,
+            sessions,
+            events,
+            queries,
+            query_duration,
+            query_nodes,
+        }
+    }
+
+    /// Reports the number of nodes rejected by the fork digest filter per foreign fork digest.
+    pub fn register_fork_digest_filter(&mut self, filter: Arc<ForkDigestFilter>) {
+        self.registry
+            .register_collector(Box::new(ForkDigestCollector { filter }));
+    }
+
+    /// Counts an event of the discv5 event stream, including TALK requests handed to the TALK
+    /// responder.
+    pub fn record_event(&self, event: &BusEvent) {
+        let r#type = match event {
+            BusEvent::Discv5(Event::Discovered(_)) => "discovered",
+            BusEvent::Discv5(Event::NodeInserted { .. }) => "node_inserted",
+            BusEvent::Discv5(Event::UnverifiableEnr { .. }) => "unverifiable_enr",
+            BusEvent::Discv5(Event::SessionEstablished(_, addr)) => {
+                let family = if addr.is_ipv6() { "ipv6" } else { "ipv4" };
+                self.sessions.get_or_create(&FamilyLabels { family }).inc();
+                "session_established"
+            }
+            BusEvent::Discv5(Event::SocketUpdated(_)) => "socket_updated",
+            BusEvent::Discv5(Event::TalkRequest(_)) | BusEvent::TalkRequest { .. } => {
+                "talk_request"
+            }
+            BusEvent::Discv5(_) => "other",
+        };
+        self.events.get_or_create(&EventLabels { r#type }).inc();
+    }
+
+    /// Records the outcome of a query run by a service.
+    pub fn record_query<E>(
+        &self,
+        source: QuerySource,
+        duration: Duration,
+        result: &Result<Vec<Enr>, E>,
+    ) {
+        let source = source.as_str();
+        let result = match result {
+            Ok(enrs) => {
+                self.query_nodes
+                    .get_or_create(&SourceLabels { source })
+                    .observe(enrs.len() as f64);
+                "success"
+            }
+            Err(_) => "failure",
+        };
+        self.queries
+            .get_or_create(&ResultLabels { source, result })
+            .inc();
+        self.query_duration
+            .get_or_create(&SourceLabels { source })
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Encodes the metrics in the OpenMetrics text format.
+    pub fn encode(&self) -> Result<String, fmt::Error> {
+        let mut buffer = String::new();
+        prometheus_client::encoding::text::encode(&mut buffer, &self.registry)?;
+        Ok(buffer)
+    }
+}
+
+/// Counts the events of the server.
+pub fn run(metrics: Arc<Metrics>, mut events: EventReceiver) {
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            metrics.record_event(&event);
+        }
+    });
+}
+
+/// The route serving the metrics at `/metrics`.
+pub fn router(metrics: Arc<Metrics>) -> Router {
+    Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(metrics)
+}
+
+/// `GET /metrics`
+async fn scrape(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    match metrics.encode() {
+        Ok(body) => ([(header::CONTENT_TYPE, CONTENT_TYPE)], body).into_response(),
+        Err(_) => axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// Reads the routing table and the metrics of discv5 on every scrape.
+struct Discv5Collector {
+    discv5: Arc<Discv5>,
+}
+
+impl fmt::Debug for Discv5Collector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Discv5Collector").finish()
+    }
+}
+
+impl Collector for Discv5Collector {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), fmt::Error> {
+        let buckets = stats::bucket_statistics(&self.discv5);
+
+        let mut family = encoder.encode_descriptor(
+            "bucket_connected_nodes",
+            "Connected nodes per routing table bucket and connection direction",
+            None,
+            MetricType::Gauge,
+        )?;
+        for bucket in &buckets {
+            let label = bucket.bucket.to_string();
+            for (direction, count) in [
+                ("incoming", bucket.incoming_peers),
+                ("outgoing", bucket.outgoing_peers),
+            ] {
+                ConstGauge::new(count as i64).encode(
+                    family
+                        .encode_family(&[("bucket", label.as_str()), ("direction", direction)])?,
+                )?;
+            }
+        }
+
+        encode_bucket_gauge(
+            &mut encoder,
+            "bucket_disconnected_nodes",
+            "Disconnected nodes per routing table bucket",
+            &buckets,
+            |bucket| bucket.disconnected_peers,
+        )?;
+        encode_bucket_gauge(
+            &mut encoder,
+            "bucket_connected_ipv6_nodes",
+            "Connected nodes with an ipv6 socket per routing table bucket",
+            &buckets,
+            |bucket| bucket.ipv6_peers,
+        )?;
+
+        let metrics = self.discv5.metrics();
+        for (name, help, value) in [
+            (
+                "connected_peers",
+                "Connected nodes in the routing table",
+                self.discv5.connected_peers() as i64,
+            ),
+            (
+                "active_sessions",
+                "Currently established sessions",
+                metrics.active_sessions as i64,
+            ),
+        ] {
+            let gauge = ConstGauge::new(value);
+            gauge.encode(encoder.encode_descriptor(name, help, None, gauge.metric_type())?)?;
+        }
+        let gauge = ConstGauge::new(metrics.unsolicited_requests_per_second);
+        gauge.encode(encoder.encode_descriptor(
+            "unsolicited_requests_per_second",
+            "Unsolicited requests received per second, averaged over a moving window",
+            None,
+            gauge.metric_type(),
+        )?)?;
+        for (name, help, value) in [
+            ("sent_bytes", "Bytes sent", metrics.bytes_sent as u64),
+            (
+                "received_bytes",
+                "Bytes received",
+                metrics.bytes_recv as u64,
+            ),
+        ] {
+            let counter = ConstCounter::new(value);
+            counter.encode(encoder.encode_descriptor(
+                name,
+                help,
+                None,
+                counter.metric_type(),
+            )?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the rejections of the fork digest filter on every scrape.
+#[derive(Debug)]
+struct ForkDigestCollector {
+    filter: Arc<ForkDigestFilter>,
+}
+
+impl Collector for ForkDigestCollector {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), fmt::Error> {
+        let mut family = encoder.encode_descriptor(
+            "fork_digest_rejected_nodes",
+            "Nodes rejected by the fork digest filter per fork digest",
+            None,
+            MetricType::Gauge,
+        )?;
+        for (fork_digest, nodes) in self.filter.rejected() {
+            ConstGauge::new(nodes as i64)
+                .encode(family.encode_family(&[("fork_digest", fork_digest.as_str())])?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Encodes a gauge with a value per bucket.
+fn encode_bucket_gauge(
+    encoder: &mut DescriptorEncoder,
+    name: &str,
+    help: &str,
+    buckets: &[BucketStatistic],
+    value: impl Fn(&BucketStatistic) -> u64,
+) -> Result<(), fmt::Error> {
+    let mut family = encoder.encode_descriptor(name, help, None, MetricType::Gauge)?;
+    for bucket in buckets {
+        let label = bucket.bucket.to_string();
+        ConstGauge::new(value(bucket) as i64)
+            .encode(family.encode_family(&[("bucket", label.as_str())])?)?;
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use serde::Serialize;
+
 /// The query service.
 pub mod query;
 
@@ -6,3 +8,43 @@ pub mod events;
 
 /// Statistics service.
 pub mod stats;
+
+/// TALK request responder service.
+pub mod talk;
+
+/// HTTP/JSON admin API.
+pub mod http;
+
+/// Prometheus metrics service.
+pub mod metrics;
+
+/// The crawl service.
+pub mod crawl;
+
+/// Export of discovered nodes service.
+pub mod export;
+
+/// Peer graphs of the discovered nodes.
+pub mod graph;
+
+/// The subnet search service.
+pub mod subnets;
+
+/// Writes the results of a service as pretty JSON to the output file, or to stdout if there is
+/// none. `what` names the results in the log.
+pub(crate) fn write_json<T: Serialize>(results: &T, output: Option<&str>, what: &str) {
+    let json = match serde_json::to_string_pretty(results) {
+        Ok(json) => json,
+        Err(e) => {
+            log::error!("Failed to encode the {}. Error: {}", what, e);
+            return;
+        }
+    };
+    match output {
+        Some(path) => match std::fs::write(path, json) {
+            Ok(()) => log::info!("Wrote the {} to {}", what, path),
+            Err(e) => log::error!("Failed to write the {}. Error: {}", what, e),
+        },
+        None => println!("{json}"),
+    }
+}
@@ -1,13 +1,89 @@
-use discv5::{enr, Discv5};
-use std::{sync::Arc, time::Duration};
+use discv5::{
+    enr::{self, NodeId},
+    Discv5, Key, QueryError,
+};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-/// Regularly queries for new peers.
-pub async fn run(discv5: Arc<Discv5>, break_time: Duration) {
+use super::{
+    crawl,
+    export::Discoveries,
+    metrics::{Metrics, QuerySource},
+};
+use crate::{
+    distance::NodeDistance,
+    findnode,
+    request_enr::{failure::Failure, target::Target},
+    server::fork_digest::ForkDigestFilter,
+    utils::{local_node::LocalNode, raw_node::RawNode},
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The number of nodes a predicate query looks for, the number returned by a regular query.
+const PREDICATE_TARGET_PEERS: usize = 16;
+
+/// Sends the lookups of the query service from short-lived nodes instead of the discv5 service,
+/// and records the nodes they find with the peers that returned them for peer graph exports.
+pub struct PeerGraph {
+    /// The discovered nodes the lookups are recorded in.
+    pub discoveries: Arc<Discoveries>,
+    /// The options of the short-lived nodes.
+    pub local_node: LocalNode,
+    /// The number of peers contacted in parallel.
+    pub parallelism: usize,
+}
+
+/// Regularly queries for new peers, recording the outcome of each query in the metrics if given.
+///
+/// With a fork digest filter, queries only return nodes of its network. With a peer graph, the
+/// peers that answered a lookup are added to the routing table.
+pub async fn run(
+    discv5: Arc<Discv5>,
+    break_time: Duration,
+    metrics: Option<Arc<Metrics>>,
+    fork_digest_filter: Option<Arc<ForkDigestFilter>>,
+    peer_graph: Option<PeerGraph>,
+) {
+    let mut nodes = Vec::new();
+    if let Some(peer_graph) = &peer_graph {
+        match crawl::start_nodes(&peer_graph.local_node, peer_graph.parallelism).await {
+            Ok(started) => nodes = started,
+            Err(e) => {
+                log::error!("Failed to start the lookup nodes. Error: {}", e);
+                return;
+            }
+        }
+    }
     loop {
         log::info!("Searching for peers...");
         let target_random_node_id = enr::NodeId::random();
-        // let unlocked_server = discv5.lock().unwrap();
-        match discv5.find_node(target_random_node_id).await {
+        let start = Instant::now();
+        let result = match &peer_graph {
+            Some(peer_graph) => {
+                let found = peer_lookup(
+                    &discv5,
+                    &mut nodes,
+                    target_random_node_id,
+                    fork_digest_filter.as_ref(),
+                )
+                .await;
+                peer_graph.discoveries.record_lookup(&found);
+                for (peer, _) in &found.responses {
+                    let _ = discv5.add_enr(peer.clone());
+                }
+                Ok(found.enrs)
+            }
+            None => lookup(&discv5, target_random_node_id, fork_digest_filter.as_ref()).await,
+        };
+        if let Some(metrics) = &metrics {
+            metrics.record_query(QuerySource::Query, start.elapsed(), &result);
+        }
+        match result {
             Err(e) => log::warn!("Find Node result failed: {e:?}"),
             Ok(found_enrs) => {
                 log::info!("Query Completed. Nodes found: {}", found_enrs.len());
@@ -16,7 +92,161 @@ pub async fn run(discv5: Arc<Discv5>, break_time: Duration) {
                 }
             }
         }
+        if let Some(filter) = &fork_digest_filter {
+            let rejected = filter.rejected();
+            if !rejected.is_empty() {
+                let counts = rejected
+                    .iter()
+                    .map(|(fork_digest, nodes)| format!("{fork_digest}: {nodes}"))
+                    .collect::<Vec<_>>();
+                log::info!("Rejected nodes of other forks: {}", counts.join(", "));
+            }
+        }
 
         tokio::time::sleep(break_time).await;
     }
 }
+
+/// Looks up the nodes closest to the target, only returning nodes of the fork digest filter's
+/// network if given.
+pub async fn lookup(
+    discv5: &Discv5,
+    target: enr::NodeId,
+    fork_digest_filter: Option<&Arc<ForkDigestFilter>>,
+) -> Result<Vec<enr::Enr<enr::CombinedKey>>, QueryError> {
+    match fork_digest_filter {
+        Some(filter) => {
+            let filter = Arc::clone(filter);
+            discv5
+                .find_node_predicate(
+                    target,
+                    Box::new(move |enr| filter.matches(enr)),
+                    PREDICATE_TARGET_PEERS,
+                )
+                .await
+        }
+        None => discv5.find_node(target).await,
+    }
+}
+
+/// The outcome of a lookup from short-lived nodes.
+#[derive(Debug, Clone, Default)]
+pub struct PeerLookup {
+    /// The closest nodes to the target that answered, closest first, only of the fork digest
+    /// filter's network if given.
+    pub enrs: Vec<Enr>,
+    /// The peers that answered, with the nodes they returned.
+    pub responses: Vec<(Enr, Vec<Enr>)>,
+    /// The peers that did not answer.
+    pub unresponsive: Vec<Enr>,
+}
+
+/// Whether a lookup contacted a node yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Contact {
+    Waiting,
+    Pending,
+    Answered,
+    Failed,
+}
+
+/// Looks up the nodes closest to the target like [`lookup`], starting from the local routing table,
+/// but sends the FINDNODE requests from the short-lived `nodes`, one request in flight per node, so
+/// that the peer that returned each node is known.
+pub async fn peer_lookup(
+    discv5: &Discv5,
+    nodes: &mut Vec<RawNode>,
+    target: NodeId,
+    fork_digest_filter: Option<&Arc<ForkDigestFilter>>,
+) -> PeerLookup {
+    let local_node_id = discv5.local_enr().node_id();
+    // The nodes found so far by their distance to the target
+    let mut candidates: BTreeMap<[u8; 32], (Enr, Contact)> = BTreeMap::new();
+    let add = |candidates: &mut BTreeMap<_, _>, enr: Enr| {
+        if enr.node_id() != local_node_id {
+            let distance = NodeDistance::between(&target, &enr.node_id()).xor;
+            candidates
+                .entry(distance)
+                .or_insert((enr, Contact::Waiting));
+        }
+    };
+    for enr in discv5.table_entries_enr() {
+        add(&mut candidates, enr);
+    }
+
+    let mut found = PeerLookup::default();
+    let mut pending = FuturesUnordered::new();
+    loop {
+        // Contact the closest nodes that have not failed, until they all answered
+        while !nodes.is_empty() {
+            let Some((enr, contact)) = candidates
+                .values_mut()
+                .filter(|(_, contact)| *contact != Contact::Failed)
+                .take(PREDICATE_TARGET_PEERS)
+                .find(|(_, contact)| *contact == Contact::Waiting)
+            else {
+                break;
+            };
+            *contact = Contact::Pending;
+            let peer = enr.clone();
+            let mut node = nodes.pop().expect("There is an idle node");
+            pending.push(async move {
+                let result = request_closest(&mut node, &peer, target).await;
+                (node, peer, result)
+            });
+        }
+        let Some((node, peer, result)) = pending.next().await else {
+            break;
+        };
+        nodes.push(node);
+        let distance = NodeDistance::between(&target, &peer.node_id()).xor;
+        match result {
+            Ok(returned) => {
+                if let Some((_, contact)) = candidates.get_mut(&distance) {
+                    *contact = Contact::Answered;
+                }
+                for enr in &returned {
+                    add(&mut candidates, enr.clone());
+                }
+                found.responses.push((peer, returned));
+            }
+            Err(failure) => {
+                log::debug!("{} did not answer. Error: {}", peer.node_id(), failure);
+                if let Some((_, contact)) = candidates.get_mut(&distance) {
+                    *contact = Contact::Failed;
+                }
+                found.unresponsive.push(peer);
+            }
+        }
+    }
+
+    found.enrs = candidates
+        .into_values()
+        .filter(|(_, contact)| *contact == Contact::Answered)
+        .map(|(enr, _)| enr)
+        .filter(|enr| fork_digest_filter.is_none_or(|filter| filter.matches(enr)))
+        .take(PREDICATE_TARGET_PEERS)
+        .collect();
+    found
+}
+
+/// Requests the nodes of a peer closest to the target: like the discv5 service, the bucket of the
+/// target in the peer's routing table and the buckets next to it.
+async fn request_closest(
+    node: &mut RawNode,
+    peer: &Enr,
+    target: NodeId,
+) -> Result<Vec<Enr>, Failure> {
+    let contact = Target::Enr(peer.clone())
+        .contact(node.ip_mode())
+        .map_err(Failure::Unreachable)?;
+    let distances = match Key::from(peer.node_id()).log2_distance(&Key::from(target)) {
+        Some(distance) => vec![distance, distance + 1, distance - 1]
+            .into_iter()
+            .filter(|&distance| (1..=256).contains(&distance))
+            .collect(),
+        None => vec![0],
+    };
+    let response = findnode::request_nodes(node, contact, distances.clone()).await?;
+    Ok(crawl::at_distances(peer, response.enrs, &distances))
+}
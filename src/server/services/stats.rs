@@ -4,32 +4,35 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::server::event_bus::EventReceiver;
+
 /// Prints discv5 server stats on a regular cadence.
-pub fn run(discv5: Arc<Discv5>, break_time: Option<Duration>, stats: u64) {
+pub fn run(
+    discv5: Arc<Discv5>,
+    mut events: EventReceiver,
+    break_time: Option<Duration>,
+    stats: u64,
+) {
     let break_time = break_time.unwrap_or_else(|| Duration::from_secs(10));
     tokio::spawn(async move {
-        let mut event_stream = discv5.event_stream().await.unwrap();
         let mut stats_interval = tokio::time::interval(break_time);
         let mut ipv6_connections = 0;
         let mut ipv4_connections = 0;
         loop {
             tokio::select! {
                 _ = stats_interval.tick() => {
-                print_global_stats(Arc::clone(&discv5), ipv6_connections, ipv4_connections);
-                print_bucket_stats(Arc::clone(&discv5), stats);
+                    print_global_stats(Arc::clone(&discv5), ipv6_connections, ipv4_connections);
+                    print_bucket_stats(Arc::clone(&discv5), stats);
                 }
-                Some(event) = event_stream.recv() => {
-                        match event {
-                      Event::SessionEstablished(_enr,addr) => {
-                    if addr.is_ipv6() {
-                        ipv6_connections += 1;
-                    } else if addr.is_ipv4() {
-                        ipv4_connections +=1;
+                Some(event) = events.recv() => {
+                    if let Some(Event::SessionEstablished(_enr, addr)) = event.discv5() {
+                        if addr.is_ipv6() {
+                            ipv6_connections += 1;
+                        } else if addr.is_ipv4() {
+                            ipv4_connections += 1;
+                        }
                     }
                 }
-                _ => {}
-                }
-            }
             }
         }
     });
@@ -63,6 +66,35 @@ fn print_global_stats(discv5: Arc<Discv5>, ipv6_connections: u64, ipv4_connectio
 
 /// Prints discv5 server stats.
 pub fn print_bucket_stats(discv5: Arc<Discv5>, stats: u64) {
+    let mut bucket_stats = bucket_statistics(&discv5);
+
+    // Sort the buckets
+    bucket_stats.sort_by_key(|stat| stat.connected_peers);
+
+    // Print only the top `stats` number of buckets
+    for bucket_stat in bucket_stats.iter().take(stats as usize) {
+        let BucketStatistic {
+            bucket,
+            connected_peers,
+            disconnected_peers,
+            incoming_peers: connected_incoming_peers,
+            outgoing_peers: connected_outgoing_peers,
+            ipv6_peers,
+        } = bucket_stat;
+        log::info!(
+            "Bucket {} statistics: Connected peers: {} (Incoming: {}, Outgoing: {}, ipv6: {}), Disconnected Peers: {}",
+            bucket,
+            connected_peers,
+            connected_incoming_peers,
+            connected_outgoing_peers,
+            ipv6_peers,
+            disconnected_peers
+        );
+    }
+}
+
+/// Computes the statistics of every non-empty bucket of the routing table.
+pub fn bucket_statistics(discv5: &Discv5) -> Vec<BucketStatistic> {
     let table_entries = discv5.table_entries();
     let self_id: discv5::Key<_> = discv5.local_enr().node_id().into();
 
@@ -117,28 +149,5 @@ pub fn print_bucket_stats(discv5: Arc<Discv5>, stats: u64) {
             ipv6_peers,
         });
     }
-
-    // Sort the buckets
-    bucket_stats.sort_by_key(|stat| stat.connected_peers);
-
-    // Print only the top `stats` number of buckets
-    for bucket_stat in bucket_stats.iter().take(stats as usize) {
-        let BucketStatistic {
-            bucket,
-            connected_peers,
-            disconnected_peers,
-            incoming_peers: connected_incoming_peers,
-            outgoing_peers: connected_outgoing_peers,
-            ipv6_peers,
-        } = bucket_stat;
-        log::info!(
-            "Bucket {} statistics: Connected peers: {} (Incoming: {}, Outgoing: {}, ipv6: {}), Disconnected Peers: {}",
-            bucket,
-            connected_peers,
-            connected_incoming_peers,
-            connected_outgoing_peers,
-            ipv6_peers,
-            disconnected_peers
-        );
-    }
+    bucket_stats
 }
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use discv5::{
+    enr::{self, NodeId},
+    Discv5,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    crawl,
+    metrics::{Metrics, QuerySource},
+};
+use crate::{
+    server::{command::SubnetSearch, fork_digest::ForkDigestFilter},
+    utils::eth2,
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// A node advertising a searched subnet.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SubnetMatch {
+    /// The hex encoded node id.
+    pub node_id: String,
+    /// The base64 encoded ENR.
+    pub enr: String,
+    /// Every attestation subnet the node advertises.
+    pub attnets: Vec<u64>,
+    /// Every sync committee subnet the node advertises.
+    pub syncnets: Vec<u64>,
+    /// When the node was found, in milliseconds since the unix epoch.
+    pub found_at: u64,
+}
+
+impl SubnetMatch {
+    fn new(enr: &Enr) -> Self {
+        SubnetMatch {
+            node_id: hex::encode(enr.node_id().raw()),
+            enr: enr.to_base64(),
+            attnets: advertised(eth2::attnets(enr)),
+            syncnets: advertised(eth2::syncnets(enr)),
+            found_at: crawl::now(),
+        }
+    }
+}
+
+/// The subnets of a bitvector field, none if it is missing or invalid.
+fn advertised(subnets: Option<Result<Vec<u64>, String>>) -> Vec<u64> {
+    subnets.and_then(Result::ok).unwrap_or_default()
+}
+
+/// Whether the node advertises any of the searched subnets.
+pub fn matches(search: &SubnetSearch, enr: &Enr) -> bool {
+    advertised(eth2::attnets(enr))
+        .iter()
+        .any(|subnet| search.attnets.contains(subnet))
+        || advertised(eth2::syncnets(enr))
+            .iter()
+            .any(|subnet| search.syncnets.contains(subnet))
+}
+
+/// Runs lookups for random node ids that only return nodes advertising any of the subnets, and of
+/// the fork digest if given, until enough nodes are found or the lookups run out. Waits
+/// `break_time` between lookups and records them in the metrics if given.
+pub async fn search(
+    discv5: Arc<Discv5>,
+    search: &SubnetSearch,
+    break_time: Duration,
+    fork_digest_filter: Option<Arc<ForkDigestFilter>>,
+    metrics: Option<Arc<Metrics>>,
+) -> Vec<SubnetMatch> {
+    let mut found = Vec::new();
+    let mut seen: HashSet<NodeId> = HashSet::new();
+    let mut queries = 0;
+    let searching = |found: usize, queries: u64| {
+        found < search.matches && (search.max_queries == 0 || queries < search.max_queries)
+    };
+    while searching(found.len(), queries) {
+        queries += 1;
+        let predicate_search = search.clone();
+        let filter = fork_digest_filter.clone();
+        let predicate = Box::new(move |enr: &Enr| {
+            matches(&predicate_search, enr)
+                && filter.as_ref().is_none_or(|filter| filter.matches(enr))
+        });
+        let start = Instant::now();
+        let result = discv5
+            .find_node_predicate(NodeId::random(), predicate, search.matches - found.len())
+            .await;
+        if let Some(metrics) = &metrics {
+            metrics.record_query(QuerySource::SubnetSearch, start.elapsed(), &result);
+        }
+        let enrs = result.unwrap_or_else(|e| {
+            log::warn!("Subnet lookup failed: {e:?}");
+            Vec::new()
+        });
+        for enr in enrs {
+            if found.len() >= search.matches || !seen.insert(enr.node_id()) {
+                continue;
+            }
+            let subnet_match = SubnetMatch::new(&enr);
+            log::info!(
+                "Found {} with attnets {:?} and syncnets {:?}: {}",
+                enr.node_id(),
+                subnet_match.attnets,
+                subnet_match.syncnets,
+                subnet_match.enr
+            );
+            found.push(subnet_match);
+        }
+        log::info!(
+            "Lookup {} completed. Matching nodes: {} of {}",
+            queries,
+            found.len(),
+            search.matches
+        );
+        if searching(found.len(), queries) {
+            tokio::time::sleep(break_time).await;
+        }
+    }
+    found
+}
+
+/// Runs the subnet search service and writes the matching nodes as JSON.
+pub async fn run(
+    discv5: Arc<Discv5>,
+    search_cmd: &SubnetSearch,
+    break_time: Duration,
+    fork_digest_filter: Option<Arc<ForkDigestFilter>>,
+    metrics: Option<Arc<Metrics>>,
+) {
+    let found = search(discv5, search_cmd, break_time, fork_digest_filter, metrics).await;
+    if found.len() < search_cmd.matches {
+        log::warn!(
+            "Found {} of {} matching nodes",
+            found.len(),
+            search_cmd.matches
+        );
+    }
+    // Report the number of matching nodes per searched subnet
+    for subnet in &search_cmd.attnets {
+        let nodes = found
+            .iter()
+            .filter(|node| node.attnets.contains(subnet))
+            .count();
+        log::info!("Nodes on attestation subnet {}: {}", subnet, nodes);
+    }
+    for subnet in &search_cmd.syncnets {
+        let nodes = found
+            .iter()
+            .filter(|node| node.syncnets.contains(subnet))
+            .count();
+        log::info!("Nodes on sync committee subnet {}: {}", subnet, nodes);
+    }
+
+    super::write_json(&found, search_cmd.output.as_deref(), "matching nodes");
+}
@@ -0,0 +1,253 @@
+use std::{path::PathBuf, process::Stdio, str::FromStr, sync::Arc, time::Duration};
+
+use discv5::TalkRequest;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::{mpsc, Mutex},
+};
+
+use crate::talk::parse_protocol;
+
+/// The number of TALK requests buffered before new requests are answered with an empty response.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// How the response to a TALK request is produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TalkHandler {
+    /// Responds with the request payload.
+    Echo,
+    /// Responds with fixed bytes.
+    Static(Vec<u8>),
+    /// Forwards the request to a long-running process started with `sh -c`.
+    Process(String),
+    /// Forwards the request to a Unix socket, using a new connection per request.
+    Unix(PathBuf),
+}
+
+/// A handler for the TALK requests of a protocol, parsed from `<protocol>=<handler>`.
+///
+/// The protocol is a utf8 name, `0x` prefixed hex bytes or `*` for any protocol. The handler is
+/// one of `echo`, `static:<hex>`, `exec:<command>` or `unix:<path>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TalkRoute {
+    /// The protocol the handler answers, or `None` for any protocol.
+    pub protocol: Option<Vec<u8>>,
+    /// The handler producing the responses.
+    pub handler: TalkHandler,
+}
+
+impl FromStr for TalkRoute {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (protocol, handler) = s.split_once('=').ok_or_else(|| {
+            eyre::eyre!("Invalid talk handler {s}, expected <protocol>=<handler>")
+        })?;
+        let protocol = match protocol {
+            "*" => None,
+            protocol => Some(parse_protocol(protocol)?),
+        };
+        let handler = match handler.split_once(':') {
+            _ if handler == "echo" => TalkHandler::Echo,
+            Some(("static", response)) => TalkHandler::Static(
+                hex::decode(response.trim_start_matches("0x"))
+                    .map_err(|_| eyre::eyre!("Invalid hex static response {response}"))?,
+            ),
+            Some(("exec", command)) if !command.is_empty() => {
+                TalkHandler::Process(command.to_string())
+            }
+            Some(("unix", path)) if !path.is_empty() => TalkHandler::Unix(PathBuf::from(path)),
+            _ => eyre::bail!(
+                "Invalid talk handler {handler}, expected echo, static:<hex>, exec:<command> or unix:<path>"
+            ),
+        };
+        Ok(TalkRoute { protocol, handler })
+    }
+}
+
+/// A handler process, restarted whenever an exchange with it fails or times out.
+struct HandlerProcess {
+    command: String,
+    /// The running process. An exchange takes the process out while it runs, so a failed or
+    /// cancelled exchange discards the process instead of leaving its reply to the next request.
+    io: Mutex<Option<ProcessIo>>,
+}
+
+/// A running handler process. Requests are written to its stdin and replies read from its stdout.
+struct ProcessIo {
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    // Kills the process once dropped
+    _child: Child,
+}
+
+impl ProcessIo {
+    /// Starts the command with `sh -c`.
+    fn spawn(command: &str) -> eyre::Result<Self> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| eyre::eyre!("Failed to start {command}: {e}"))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(ProcessIo {
+            stdin,
+            stdout: BufReader::new(stdout),
+            _child: child,
+        })
+    }
+
+    /// Writes the request line and reads the reply line.
+    async fn exchange(&mut self, line: &str) -> eyre::Result<String> {
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await?;
+        let mut reply = String::new();
+        if self.stdout.read_line(&mut reply).await? == 0 {
+            eyre::bail!("The handler process exited");
+        }
+        Ok(reply)
+    }
+}
+
+/// A handler with any external process it forwards to.
+enum Responder {
+    Echo,
+    Static(Vec<u8>),
+    Process(Box<HandlerProcess>),
+    Unix(PathBuf),
+}
+
+impl Responder {
+    /// Starts any external process of the handler.
+    fn start(handler: TalkHandler) -> eyre::Result<Self> {
+        Ok(match handler {
+            TalkHandler::Echo => Responder::Echo,
+            TalkHandler::Static(response) => Responder::Static(response),
+            TalkHandler::Process(command) => {
+                let io = ProcessIo::spawn(&command)?;
+                Responder::Process(Box::new(HandlerProcess {
+                    command,
+                    io: Mutex::new(Some(io)),
+                }))
+            }
+            TalkHandler::Unix(path) => Responder::Unix(path),
+        })
+    }
+
+    /// Produces the response to a request.
+    ///
+    /// External handlers receive the request as a single line of JSON with the hex encoded
+    /// `node_id`, `protocol` and `request`, and reply with a single line holding the hex encoded
+    /// response.
+    async fn respond(&self, request: &TalkRequest) -> eyre::Result<Vec<u8>> {
+        let line = || {
+            let mut line = serde_json::json!({
+                "node_id": format!("0x{}", hex::encode(request.node_id().raw())),
+                "protocol": format!("0x{}", hex::encode(request.protocol())),
+                "request": format!("0x{}", hex::encode(request.body())),
+            })
+            .to_string();
+            line.push('\n');
+            line
+        };
+        let reply = match self {
+            Responder::Echo => return Ok(request.body().to_vec()),
+            Responder::Static(response) => return Ok(response.clone()),
+            Responder::Process(process) => {
+                let mut slot = process.io.lock().await;
+                let mut io = match slot.take() {
+                    Some(io) => io,
+                    None => {
+                        log::info!("Restarting talk handler: {}", process.command);
+                        ProcessIo::spawn(&process.command)?
+                    }
+                };
+                let reply = io.exchange(&line()).await?;
+                *slot = Some(io);
+                reply
+            }
+            Responder::Unix(path) => {
+                let stream = UnixStream::connect(path).await?;
+                let (reader, mut writer) = stream.into_split();
+                writer.write_all(line().as_bytes()).await?;
+                let mut reply = String::new();
+                BufReader::new(reader).read_line(&mut reply).await?;
+                reply
+            }
+        };
+        hex::decode(reply.trim().trim_start_matches("0x"))
+            .map_err(|_| eyre::eyre!("The handler replied with invalid hex"))
+    }
+}
+
+/// Starts the TALK responder service and returns the channel TALK requests are answered from.
+///
+/// Each request is answered by the first route matching its protocol. Requests without a
+/// matching route, or whose handler fails or does not answer within the timeout, are answered
+/// with an empty response.
+pub fn start(routes: Vec<TalkRoute>, timeout: Duration) -> eyre::Result<mpsc::Sender<TalkRequest>> {
+    let responders = routes
+        .into_iter()
+        .map(|route| Ok((route.protocol, Responder::start(route.handler)?)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let responders = Arc::new(responders);
+
+    let (sender, mut requests) = mpsc::channel::<TalkRequest>(CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(request) = requests.recv().await {
+            let responders = Arc::clone(&responders);
+            tokio::spawn(async move {
+                let protocol = format!("0x{}", hex::encode(request.protocol()));
+                let node_id = request.node_id().to_string();
+                let Some((_, responder)) = responders.iter().find(|(route, _)| {
+                    route
+                        .as_ref()
+                        .is_none_or(|route| route.as_slice() == request.protocol())
+                }) else {
+                    log::info!(
+                        "No talk handler for protocol {} requested by peer: {}",
+                        protocol,
+                        node_id
+                    );
+                    return;
+                };
+                let response =
+                    match tokio::time::timeout(timeout, responder.respond(&request)).await {
+                        Ok(Ok(response)) => response,
+                        Ok(Err(e)) => {
+                            log::warn!(
+                                "Talk handler for protocol {} failed. Error: {}",
+                                protocol,
+                                e
+                            );
+                            Vec::new()
+                        }
+                        Err(_) => {
+                            log::warn!(
+                                "Talk handler for protocol {} did not answer within {:?}",
+                                protocol,
+                                timeout
+                            );
+                            Vec::new()
+                        }
+                    };
+                log::info!(
+                    "Answered talk request for protocol {} from peer: {} with {} bytes",
+                    protocol,
+                    node_id,
+                    response.len()
+                );
+                if let Err(e) = request.respond(response) {
+                    log::warn!("Failed to send the talk response. Error: {:?}", e);
+                }
+            });
+        }
+    });
+    Ok(sender)
+}
@@ -0,0 +1,108 @@
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use discv5::{enr, ConfigBuilder, RateLimiterBuilder};
+
+use super::command::{Discv5Tuning, TableFilter};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The largest number of nodes in a bucket.
+const MAX_NODES_PER_BUCKET: usize = 16;
+
+/// Applies the protocol settings to the discv5 configuration.
+pub fn configure(tuning: &Discv5Tuning, builder: &mut ConfigBuilder) -> eyre::Result<()> {
+    if tuning.incoming_bucket_limit > MAX_NODES_PER_BUCKET {
+        eyre::bail!(
+            "The incoming bucket limit cannot exceed the bucket size of {}",
+            MAX_NODES_PER_BUCKET
+        );
+    }
+
+    builder
+        .request_timeout(Duration::from_secs(tuning.request_timeout))
+        .vote_duration(Duration::from_secs(tuning.vote_duration))
+        .query_parallelism(tuning.query_parallelism)
+        .query_peer_timeout(Duration::from_secs(tuning.query_peer_timeout))
+        .query_timeout(Duration::from_secs(tuning.query_timeout))
+        .session_cache_capacity(tuning.session_cache_capacity)
+        .session_timeout(Duration::from_secs(tuning.session_timeout))
+        .ping_interval(Duration::from_secs(tuning.ping_interval))
+        .table_filter(table_filter(tuning.table_filter))
+        .incoming_bucket_limit(tuning.incoming_bucket_limit)
+        .ban_duration(match tuning.ban_duration {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        });
+    if tuning.ip_limit {
+        builder.ip_limit();
+    }
+
+    if tuning.packet_filter {
+        builder
+            .enable_packet_filter()
+            .filter_rate_limiter(rate_limiter(tuning)?)
+            .filter_max_nodes_per_ip(Some(tuning.filter_max_nodes_per_ip).filter(|max| *max > 0))
+            .filter_max_bans_per_ip(Some(tuning.filter_max_bans_per_ip).filter(|max| *max > 0));
+    }
+    Ok(())
+}
+
+/// The inbound request rate limits of the packet filter, if enabled.
+fn rate_limiter(tuning: &Discv5Tuning) -> eyre::Result<Option<discv5::RateLimiter>> {
+    if tuning.filter_total_rate == 0 {
+        return Ok(None);
+    }
+    let second = Duration::from_secs(1);
+    let mut limiter = RateLimiterBuilder::new().total_n_every(tuning.filter_total_rate, second);
+    if tuning.filter_node_rate > 0 {
+        limiter = limiter.node_n_every(tuning.filter_node_rate, second);
+    }
+    if tuning.filter_ip_rate > 0 {
+        limiter = limiter.ip_n_every(tuning.filter_ip_rate, second);
+    }
+    limiter.build().map(Some).map_err(|e| eyre::eyre!(e))
+}
+
+/// The routing table filter for the chosen nodes.
+pub fn table_filter(filter: TableFilter) -> fn(&Enr) -> bool {
+    match filter {
+        TableFilter::Any => |_| true,
+        TableFilter::Ip4 => |enr| enr.udp4_socket().is_some(),
+        TableFilter::Ip6 => |enr| enr.udp6_socket().is_some(),
+        TableFilter::Public => |enr| {
+            enr.udp4_socket()
+                .is_some_and(|socket| is_public_ip4(socket.ip()))
+                || enr
+                    .udp6_socket()
+                    .is_some_and(|socket| is_public_ip6(socket.ip()))
+        },
+    }
+}
+
+/// Whether an ipv4 address is publicly routable.
+fn is_public_ip4(ip: &Ipv4Addr) -> bool {
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // Shared address space used for carrier-grade NAT
+        || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64))
+}
+
+/// Whether an ipv6 address is publicly routable.
+fn is_public_ip6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local addresses
+        || first & 0xfe00 == 0xfc00
+        // Link local addresses
+        || first & 0xffc0 == 0xfe80)
+}
@@ -0,0 +1,51 @@
+use clap::{Args, ValueEnum};
+
+use crate::utils::local_node::LocalNode;
+
+/// The encoding of a TALKREQ payload given on the command line.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum PayloadEncoding {
+    /// Hex encoded bytes, with or without a 0x prefix
+    #[default]
+    Hex,
+    /// A utf8 string
+    Utf8,
+}
+
+/// Talk
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Talk {
+    /// The node to send the TALKREQ to.
+    #[clap(
+        short = 'm',
+        long = "target",
+        allow_hyphen_values = true,
+        help = "The node to send the request to, as an ENR, multiaddr, enode URL or <public-key>@<ip>:<port>"
+    )]
+    pub target: String,
+    /// The protocol name of the request.
+    #[clap(
+        long = "protocol",
+        help = "The protocol name of the request. A 0x prefixed value is sent as hex encoded bytes, e.g. 0x500b for the Portal history network."
+    )]
+    pub protocol: String,
+    /// The request payload.
+    #[clap(
+        short = 'd',
+        long = "payload",
+        help = "The payload of the request.",
+        default_value = ""
+    )]
+    pub payload: String,
+    /// The encoding of the request payload.
+    #[clap(
+        short = 'e',
+        long = "encoding",
+        help = "The encoding of the request payload.",
+        default_value = "hex"
+    )]
+    pub encoding: PayloadEncoding,
+    /// The local node the request is sent from.
+    #[clap(flatten)]
+    pub local_node: LocalNode,
+}
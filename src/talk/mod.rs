@@ -0,0 +1,126 @@
+//! Handles sending TALKREQ messages to a node
+
+use std::time::Instant;
+
+use alloy_rlp::Header;
+
+use crate::request_enr::target::Target;
+
+/// The [clap] cli command arguments for the talk service.
+pub mod command;
+pub use command::*;
+
+/// Converts a protocol name into the bytes sent on the wire. A `0x` prefixed name is hex decoded.
+pub fn parse_protocol(protocol: &str) -> eyre::Result<Vec<u8>> {
+    match protocol.strip_prefix("0x") {
+        Some(encoded) => {
+            hex::decode(encoded).map_err(|_| eyre::eyre!("Invalid hex protocol {protocol}"))
+        }
+        None => Ok(protocol.as_bytes().to_vec()),
+    }
+}
+
+/// Decodes a request payload given in the encoding.
+pub fn parse_payload(payload: &str, encoding: PayloadEncoding) -> eyre::Result<Vec<u8>> {
+    match encoding {
+        PayloadEncoding::Hex => hex::decode(payload.trim_start_matches("0x"))
+            .map_err(|_| eyre::eyre!("Invalid hex payload")),
+        PayloadEncoding::Utf8 => Ok(payload.as_bytes().to_vec()),
+    }
+}
+
+/// Renders RLP encoded bytes as nested lists of hex strings, e.g. `[0x01, [0x, 0xabcd]]`.
+/// Returns `None` if the bytes are not a single valid RLP item.
+pub fn decode_rlp(bytes: &[u8]) -> Option<String> {
+    let mut buf = bytes;
+    let decoded = decode_rlp_item(&mut buf).ok()?;
+    buf.is_empty().then_some(decoded)
+}
+
+/// Decodes the next RLP item of the buffer, advancing it.
+fn decode_rlp_item(buf: &mut &[u8]) -> Result<String, alloy_rlp::Error> {
+    let header = Header::decode(buf)?;
+    if buf.len() < header.payload_length {
+        return Err(alloy_rlp::Error::InputTooShort);
+    }
+    let (payload, rest) = buf.split_at(header.payload_length);
+    *buf = rest;
+    if !header.list {
+        return Ok(format!("0x{}", hex::encode(payload)));
+    }
+    let mut payload = payload;
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        items.push(decode_rlp_item(&mut payload)?);
+    }
+    Ok(format!("[{}]", items.join(", ")))
+}
+
+/// Runs the talk command. Returns whether the node answered the TALKREQ.
+pub async fn run(talk: &Talk) -> bool {
+    let target = match talk.target.parse::<Target>() {
+        Ok(target) => target,
+        Err(e) => {
+            log::error!("Invalid target provided. Error: {}", e);
+            return false;
+        }
+    };
+    let (protocol, payload) = match (
+        parse_protocol(&talk.protocol),
+        parse_payload(&talk.payload, talk.encoding),
+    ) {
+        (Ok(protocol), Ok(payload)) => (protocol, payload),
+        (Err(e), _) | (_, Err(e)) => {
+            log::error!("Invalid request provided. Error: {}", e);
+            return false;
+        }
+    };
+
+    let discv5 = match talk.local_node.start(target.is_ipv6(), |_| {}).await {
+        Ok(discv5) => discv5,
+        Err(e) => {
+            log::error!("Failed to start the local node. Error: {}", e);
+            return false;
+        }
+    };
+    let enr = match target.resolve(&discv5).await {
+        Ok(enr) => enr,
+        Err(e) => {
+            log::error!("Failed to obtain the ENR of the target. Error: {}", e);
+            return false;
+        }
+    };
+
+    log::info!(
+        "Sending TALKREQ with protocol 0x{} and {} payload bytes to {}",
+        hex::encode(&protocol),
+        payload.len(),
+        enr.node_id()
+    );
+    let start = Instant::now();
+    let response = match discv5.talk_req(enr, protocol, payload).await {
+        Ok(response) => response,
+        Err(e) => {
+            log::error!("TALKREQ failed. Error: {}", e);
+            return false;
+        }
+    };
+    log::info!(
+        "Received TALKRESP with {} bytes in {:.2}ms",
+        response.len(),
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+
+    if response.is_empty() {
+        log::info!("The response is empty, the node may not support the protocol");
+        return true;
+    }
+    println!("0x{}", hex::encode(&response));
+    if let Ok(utf8) = std::str::from_utf8(&response) {
+        log::info!("UTF8: {}", utf8);
+    }
+    if let Some(rlp) = decode_rlp(&response) {
+        log::info!("RLP: {}", rlp);
+    }
+    true
+}
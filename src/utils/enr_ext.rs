@@ -22,11 +22,19 @@ pub trait CombinedKeyPublicExt {
     fn into_peer_id(&self) -> PeerId;
 }
 
+/// Extend libp2p `PeerId` for conversion to ENR types.
+pub trait PeerIdExt {
+    /// Recovers the public key inlined in the peer id.
+    fn to_combined_public_key(&self) -> Result<CombinedPublicKey, &'static str>;
+}
+
 /// Extend ENR CombinedKey for conversion to libp2p keys.
-#[allow(dead_code)]
 pub trait CombinedKeyExt {
     /// Converts a libp2p key into an ENR combined key.
     fn from_libp2p(key: &Keypair) -> Result<CombinedKey, &'static str>;
+
+    /// Converts the ENR combined key into a libp2p key.
+    fn to_libp2p(&self) -> Result<Keypair, &'static str>;
 }
 
 impl EnrExt for Enr {
@@ -47,14 +55,14 @@ impl EnrExt for Enr {
             if let Some(udp) = self.udp4() {
                 let mut multiaddr: Multiaddr = ip.into();
                 multiaddr.push(Protocol::Udp(udp));
-                multiaddr.push(Protocol::P2p(peer_id.into()));
+                multiaddr.push(Protocol::P2p(peer_id));
                 multiaddrs.push(multiaddr);
             }
 
             if let Some(tcp) = self.tcp4() {
                 let mut multiaddr: Multiaddr = ip.into();
                 multiaddr.push(Protocol::Tcp(tcp));
-                multiaddr.push(Protocol::P2p(peer_id.into()));
+                multiaddr.push(Protocol::P2p(peer_id));
                 multiaddrs.push(multiaddr);
             }
         }
@@ -62,14 +70,14 @@ impl EnrExt for Enr {
             if let Some(udp6) = self.udp6() {
                 let mut multiaddr: Multiaddr = ip6.into();
                 multiaddr.push(Protocol::Udp(udp6));
-                multiaddr.push(Protocol::P2p(peer_id.into()));
+                multiaddr.push(Protocol::P2p(peer_id));
                 multiaddrs.push(multiaddr);
             }
 
             if let Some(tcp6) = self.tcp6() {
                 let mut multiaddr: Multiaddr = ip6.into();
                 multiaddr.push(Protocol::Tcp(tcp6));
-                multiaddr.push(Protocol::P2p(peer_id.into()));
+                multiaddr.push(Protocol::P2p(peer_id));
                 multiaddrs.push(multiaddr);
             }
         }
@@ -120,4 +128,43 @@ impl CombinedKeyExt for CombinedKey {
             Err("ENR: Unsupported libp2p key type")
         }
     }
+
+    fn to_libp2p(&self) -> Result<Keypair, &'static str> {
+        match self {
+            Self::Secp256k1(key) => {
+                let secret =
+                    libp2p_identity::secp256k1::SecretKey::try_from_bytes(key.to_bytes().to_vec())
+                        .map_err(|_| "libp2p: Invalid secp256k1 key")?;
+                Ok(libp2p_identity::secp256k1::Keypair::from(secret).into())
+            }
+            Self::Ed25519(key) => {
+                let secret = libp2p_identity::ed25519::SecretKey::try_from_bytes(key.to_bytes())
+                    .map_err(|_| "libp2p: Invalid ed25519 key")?;
+                Ok(libp2p_identity::ed25519::Keypair::from(secret).into())
+            }
+        }
+    }
+}
+
+impl PeerIdExt for PeerId {
+    fn to_combined_public_key(&self) -> Result<CombinedPublicKey, &'static str> {
+        // secp256k1 and ed25519 peer ids inline the public key using the identity hash.
+        let multihash = libp2p_core::multihash::Multihash::<64>::from(*self);
+        if multihash.code() != 0 {
+            return Err("PeerId: The public key is not inlined");
+        }
+        let public_key = libp2p_identity::PublicKey::try_decode_protobuf(multihash.digest())
+            .map_err(|_| "PeerId: Invalid public key")?;
+        if let Ok(pk) = public_key.clone().try_into_secp256k1() {
+            discv5::enr::k256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.to_bytes())
+                .map(CombinedPublicKey::Secp256k1)
+                .map_err(|_| "PeerId: Invalid secp256k1 public key")
+        } else if let Ok(pk) = public_key.try_into_ed25519() {
+            discv5::enr::ed25519_dalek::VerifyingKey::from_bytes(&pk.to_bytes())
+                .map(CombinedPublicKey::Ed25519)
+                .map_err(|_| "PeerId: Invalid ed25519 public key")
+        } else {
+            Err("PeerId: Unsupported key type")
+        }
+    }
 }
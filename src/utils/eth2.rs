@@ -0,0 +1,97 @@
+use std::convert::TryInto;
+
+use discv5::enr::{self, CombinedKey};
+
+type Enr = enr::Enr<CombinedKey>;
+
+/// The epoch of a fork that is not scheduled.
+pub const FAR_FUTURE_EPOCH: u64 = u64::MAX;
+
+/// The number of attestation subnets, the length of the `attnets` bitfield.
+pub const ATTESTATION_SUBNET_COUNT: u64 = 64;
+
+/// The number of sync committee subnets, the length of the `syncnets` bitfield.
+pub const SYNC_COMMITTEE_SUBNET_COUNT: u64 = 4;
+
+/// The SSZ encoded `ENRForkID` in the `eth2` field of consensus layer ENRs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EnrForkId {
+    /// The digest of the current fork and the genesis validators root.
+    pub fork_digest: [u8; 4],
+    /// The version of the next scheduled fork, or the current one if none is scheduled.
+    pub next_fork_version: [u8; 4],
+    /// The epoch of the next scheduled fork, [`FAR_FUTURE_EPOCH`] if none is scheduled.
+    pub next_fork_epoch: u64,
+}
+
+impl EnrForkId {
+    /// Decodes the SSZ encoding of the fork id.
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != 16 {
+            return Err(format!(
+                "Invalid eth2 field of {} bytes, expected 16",
+                bytes.len()
+            ));
+        }
+        Ok(EnrForkId {
+            fork_digest: bytes[0..4].try_into().expect("The length was checked"),
+            next_fork_version: bytes[4..8].try_into().expect("The length was checked"),
+            next_fork_epoch: u64::from_le_bytes(
+                bytes[8..16].try_into().expect("The length was checked"),
+            ),
+        })
+    }
+
+    /// Reads the fork id from the `eth2` field of an ENR, if it is set.
+    pub fn from_enr(enr: &Enr) -> Option<Result<Self, String>> {
+        enr.get_decodable::<alloy_rlp::Bytes>("eth2").map(|bytes| {
+            bytes
+                .map_err(|e| format!("Invalid eth2 field: {e}"))
+                .and_then(|bytes| Self::decode(&bytes))
+        })
+    }
+
+    /// Whether another fork is scheduled.
+    pub fn has_next_fork(&self) -> bool {
+        self.next_fork_epoch != FAR_FUTURE_EPOCH
+    }
+}
+
+/// Decodes an SSZ encoded bitvector of `length` bits into the indices of its set bits.
+pub fn decode_bitvector(bytes: &[u8], length: u64) -> Result<Vec<u64>, String> {
+    let expected = (length as usize).div_ceil(8);
+    if bytes.len() != expected {
+        return Err(format!(
+            "Invalid bitvector of {} bytes, expected {}",
+            bytes.len(),
+            expected
+        ));
+    }
+    let bits: Vec<u64> = (0..expected as u64 * 8)
+        .filter(|bit| bytes[(bit / 8) as usize] & (1 << (bit % 8)) != 0)
+        .collect();
+    if bits.last().is_some_and(|bit| *bit >= length) {
+        return Err(format!("Invalid bitvector with bits set beyond {length}"));
+    }
+    Ok(bits)
+}
+
+/// Reads the attestation subnets from the `attnets` field of an ENR, if it is set.
+pub fn attnets(enr: &Enr) -> Option<Result<Vec<u64>, String>> {
+    bitvector(enr, "attnets", ATTESTATION_SUBNET_COUNT)
+}
+
+/// Reads the sync committee subnets from the `syncnets` field of an ENR, if it is set.
+pub fn syncnets(enr: &Enr) -> Option<Result<Vec<u64>, String>> {
+    bitvector(enr, "syncnets", SYNC_COMMITTEE_SUBNET_COUNT)
+}
+
+/// Reads a bitvector field of an ENR.
+fn bitvector(enr: &Enr, key: &str, length: u64) -> Option<Result<Vec<u64>, String>> {
+    enr.get_decodable::<alloy_rlp::Bytes>(key).map(|bytes| {
+        bytes
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| decode_bitvector(&bytes, length))
+            .map_err(|e| format!("Invalid {key} field: {e}"))
+    })
+}
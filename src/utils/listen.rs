@@ -0,0 +1,34 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+use discv5::ListenConfig;
+
+/// Splits a comma separated list of addresses into its last ipv4 and ipv6 address.
+pub fn parse_addresses(addresses: &str) -> eyre::Result<(Option<Ipv4Addr>, Option<Ipv6Addr>)> {
+    let mut ipv4_address = None;
+    let mut ipv6_address = None;
+    for address in addresses.split(',') {
+        match address
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|_| eyre::eyre!("Invalid listening address: {address}"))?
+        {
+            IpAddr::V4(ip) => ipv4_address = Some(ip),
+            IpAddr::V6(ip) => ipv6_address = Some(ip),
+        }
+    }
+    Ok((ipv4_address, ipv6_address))
+}
+
+/// Builds a single or dual-stack [`ListenConfig`]. The ipv6 socket uses `port_v6` if given,
+/// otherwise `port`.
+pub fn listen_config(
+    ipv4_address: Option<Ipv4Addr>,
+    ipv6_address: Option<Ipv6Addr>,
+    port: u16,
+    port_v6: Option<u16>,
+) -> ListenConfig {
+    ListenConfig::from_two_sockets(
+        ipv4_address.map(|v| SocketAddrV4::new(v, port)),
+        ipv6_address.map(|v| SocketAddrV6::new(v, port_v6.unwrap_or(port), 0, 0)),
+    )
+}
@@ -0,0 +1,102 @@
+use std::net::Ipv6Addr;
+
+use clap::Args;
+use discv5::{enr, Config, ConfigBuilder, Discv5};
+
+use super::listen;
+
+/// The options of the short-lived local discv5 node that commands send their requests from.
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LocalNode {
+    /// Specifies the listening address(es) of the server.
+    #[clap(
+        short = 'l',
+        long = "listen-addresses",
+        alias = "listen-address",
+        help = "Specifies the listening address(es) of the server. A comma separated string can specify ipv4 and ipv6 addresses for dual stack. An ipv6 target adds the unspecified ipv6 address if none is given.",
+        default_value = "0.0.0.0"
+    )]
+    pub listen_addresses: String,
+    /// Specifies the listening UDP port of the server.
+    #[clap(
+        short = 'p',
+        long = "listen-port",
+        help = "Specifies the listening UDP port of the server.",
+        default_value = "9001"
+    )]
+    pub listen_port: u16,
+    /// Optionally specify the listening ipv6 port.
+    #[clap(
+        long = "listen-port-v6",
+        help = "Specifies the listening UDP port of the server if an ipv6 address is specified as a listening address."
+    )]
+    pub listen_port_v6: Option<u16>,
+}
+
+impl LocalNode {
+    /// Builds the ENR, key and discv5 config of the local node.
+    ///
+    /// If the target is only reachable over ipv6 and no ipv6 listening address is specified, the
+    /// node also listens on the unspecified ipv6 address. The discv5 config can be adjusted with
+    /// `configure` before it is built.
+    pub fn build(
+        &self,
+        ipv6_target: bool,
+        configure: impl FnOnce(&mut ConfigBuilder),
+    ) -> eyre::Result<(enr::Enr<enr::CombinedKey>, enr::CombinedKey, Config)> {
+        let (ipv4_address, mut ipv6_address) = listen::parse_addresses(&self.listen_addresses)?;
+
+        // An ipv6 target can only be reached from an ipv6 socket
+        if ipv6_target && ipv6_address.is_none() {
+            log::info!(
+                "Target is ipv6, also listening on {}",
+                Ipv6Addr::UNSPECIFIED
+            );
+            ipv6_address = Some(Ipv6Addr::UNSPECIFIED);
+        }
+
+        let listen_port = self.listen_port;
+        let listen_port_v6 = self.listen_port_v6.unwrap_or(listen_port);
+        let listen_config = listen::listen_config(
+            ipv4_address,
+            ipv6_address,
+            listen_port,
+            Some(listen_port_v6),
+        );
+        let enr_key = enr::CombinedKey::generate_secp256k1();
+
+        // Build a local ENR
+        let mut builder = enr::Builder::default();
+        if let Some(ip) = ipv4_address {
+            builder.ip4(ip).udp4(listen_port);
+        }
+        if let Some(ip) = ipv6_address {
+            builder.ip6(ip).udp6(listen_port_v6);
+        }
+        let enr = builder.build(&enr_key)?;
+
+        let mut config = ConfigBuilder::new(listen_config);
+        configure(&mut config);
+        Ok((enr, enr_key, config.build()))
+    }
+
+    /// Starts the local discv5 node.
+    ///
+    /// See [`LocalNode::build`] for how the node is configured.
+    pub async fn start(
+        &self,
+        ipv6_target: bool,
+        configure: impl FnOnce(&mut ConfigBuilder),
+    ) -> eyre::Result<Discv5> {
+        // Construct the discv5 service
+        let (enr, enr_key, config) = self.build(ipv6_target, configure)?;
+        let mut discv5: Discv5 = Discv5::new(enr, enr_key, config).map_err(|e| eyre::eyre!(e))?;
+
+        // Start the server
+        discv5
+            .start()
+            .await
+            .map_err(|e| eyre::eyre!("Failed to start the local node: {e:?}"))?;
+        Ok(discv5)
+    }
+}
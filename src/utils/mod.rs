@@ -1,2 +1,20 @@
 /// Logging Utilities
 pub mod logging;
+
+/// Listening address utilities
+pub mod listen;
+
+/// The short-lived local node used to send requests
+pub mod local_node;
+
+/// Extensions converting ENR types into libp2p types
+pub mod enr_ext;
+
+/// Parsing of node ids from ENRs, keys and peer ids
+pub mod node_id;
+
+/// A local node driving the discv5 session handler directly
+pub mod raw_node;
+
+/// Decoding of the Ethereum consensus ENR fields
+pub mod eth2;
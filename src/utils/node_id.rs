@@ -0,0 +1,52 @@
+use std::str::FromStr;
+
+use discv5::enr::{CombinedKey, CombinedPublicKey, Enr, EnrKey, NodeId};
+use libp2p_identity::PeerId;
+
+use super::enr_ext::PeerIdExt;
+
+/// Parses a node id from any identifier of a node.
+///
+/// Accepts a base64 ENR, a hex node id, a hex compressed or uncompressed secp256k1 public key, a
+/// libp2p peer id or a hex secp256k1 private key prefixed with `key:`.
+pub fn parse(input: &str) -> eyre::Result<NodeId> {
+    let input = input.trim();
+    if input.starts_with("enr:") {
+        let enr = Enr::<CombinedKey>::from_str(input).map_err(|e| eyre::eyre!(e))?;
+        return Ok(enr.node_id());
+    }
+    if let Some(private_key) = input.strip_prefix("key:") {
+        let mut raw_key = hex::decode(private_key.trim_start_matches("0x"))
+            .map_err(|_| eyre::eyre!("Invalid hex bytes for private key"))?;
+        let key = CombinedKey::secp256k1_from_bytes(&mut raw_key)
+            .map_err(|_| eyre::eyre!("Invalid secp256k1 private key"))?;
+        return Ok(NodeId::from(key.public()));
+    }
+    if let Ok(peer_id) = PeerId::from_str(input) {
+        let public_key = peer_id
+            .to_combined_public_key()
+            .map_err(|e| eyre::eyre!(e))?;
+        return Ok(NodeId::from(public_key));
+    }
+
+    let raw = hex::decode(input.trim_start_matches("0x"))
+        .map_err(|_| eyre::eyre!("Unrecognised node identifier: {input}"))?;
+    match raw.len() {
+        32 => Ok(NodeId::parse(&raw).map_err(|e| eyre::eyre!(e))?),
+        33 | 64 | 65 => Ok(NodeId::from(secp256k1_public_key(&raw)?)),
+        _ => Err(eyre::eyre!("Unrecognised node identifier: {input}")),
+    }
+}
+
+/// Parses a compressed or uncompressed secp256k1 public key. Uncompressed keys may omit the
+/// `0x04` tag, as they do in enode URLs.
+pub fn secp256k1_public_key(raw: &[u8]) -> eyre::Result<CombinedPublicKey> {
+    let public_key = discv5::enr::k256::ecdsa::VerifyingKey::from_sec1_bytes(raw)
+        .or_else(|_| {
+            let mut tagged = vec![4];
+            tagged.extend_from_slice(raw);
+            discv5::enr::k256::ecdsa::VerifyingKey::from_sec1_bytes(&tagged)
+        })
+        .map_err(|_| eyre::eyre!("Invalid secp256k1 public key"))?;
+    Ok(CombinedPublicKey::Secp256k1(public_key))
+}
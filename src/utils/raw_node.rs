@@ -0,0 +1,194 @@
+use std::{num::NonZeroU16, sync::Arc, time::Duration};
+
+use discv5::{
+    enr::{self, CombinedKey},
+    handler::{Handler, HandlerIn, HandlerOut, NodeAddress, NodeContact},
+    rpc::{Request, RequestBody, RequestId, Response, ResponseBody},
+    DefaultProtocolId, IpMode, ListenConfig, RequestError,
+};
+use parking_lot::RwLock;
+use tokio::sync::{mpsc, oneshot};
+
+use super::local_node::LocalNode;
+
+type Enr = enr::Enr<CombinedKey>;
+
+/// A short-lived local node that drives the discv5 session handler directly instead of the discv5
+/// service.
+///
+/// This exposes every response packet and sends requests exactly as given, but the node has no
+/// routing table and answers incoming requests with only its own ENR.
+pub struct RawNode {
+    enr: Arc<RwLock<Enr>>,
+    key: Arc<RwLock<CombinedKey>>,
+    ip_mode: IpMode,
+    exit: Option<oneshot::Sender<()>>,
+    handler_send: mpsc::UnboundedSender<HandlerIn>,
+    handler_recv: mpsc::Receiver<HandlerOut>,
+    received: Vec<(NodeAddress, Request)>,
+}
+
+impl RawNode {
+    /// Starts the session handler of a local node with a freshly generated key.
+    pub async fn start(local_node: &LocalNode, ipv6_target: bool) -> eyre::Result<Self> {
+        let (enr, key, config) = local_node.build(ipv6_target, |_| {})?;
+        let ip_mode = match config.listen_config {
+            ListenConfig::Ipv4 { .. } => IpMode::Ip4,
+            ListenConfig::Ipv6 { .. } => IpMode::Ip6,
+            ListenConfig::DualStack { .. } => IpMode::DualStack,
+        };
+        let enr = Arc::new(RwLock::new(enr));
+        let key = Arc::new(RwLock::new(key));
+        let (exit, handler_send, handler_recv) =
+            Handler::spawn::<DefaultProtocolId>(Arc::clone(&enr), Arc::clone(&key), config).await?;
+        Ok(RawNode {
+            enr,
+            key,
+            ip_mode,
+            exit: Some(exit),
+            handler_send,
+            handler_recv,
+            received: Vec::new(),
+        })
+    }
+
+    /// The current ENR of the node.
+    pub fn enr(&self) -> Enr {
+        self.enr.read().clone()
+    }
+
+    /// The address families the node listens on.
+    pub fn ip_mode(&self) -> IpMode {
+        self.ip_mode
+    }
+
+    /// The requests received from other nodes so far.
+    pub fn received(&self) -> &[(NodeAddress, Request)] {
+        &self.received
+    }
+
+    /// Sets a field of the local ENR, which increments its sequence number. Returns the new
+    /// sequence number.
+    pub fn update_enr(&self, key: &str, value: &[u8]) -> eyre::Result<u64> {
+        let mut enr = self.enr.write();
+        enr.insert(key, &value, &self.key.read())?;
+        Ok(enr.seq())
+    }
+
+    /// Sends a request and waits for its responses.
+    ///
+    /// All packets of a multi-packet NODES response are returned. If only some of them arrive
+    /// before the request times out, the received packets are returned.
+    pub async fn request(
+        &mut self,
+        contact: NodeContact,
+        body: RequestBody,
+    ) -> Result<Vec<ResponseBody>, RequestError> {
+        let id = RequestId::random();
+        let request = Request {
+            id: id.clone(),
+            body,
+        };
+        self.handler_send
+            .send(HandlerIn::Request(contact, Box::new(request)))
+            .map_err(|_| RequestError::ChannelFailed("The session handler stopped".into()))?;
+
+        let mut responses = Vec::new();
+        loop {
+            let event =
+                self.handler_recv.recv().await.ok_or_else(|| {
+                    RequestError::ChannelFailed("The session handler stopped".into())
+                })?;
+            match event {
+                HandlerOut::Response(_, response) if response.id == id => {
+                    let total = match &response.body {
+                        ResponseBody::Nodes { total, .. } => *total,
+                        _ => 1,
+                    };
+                    responses.push(response.body);
+                    if responses.len() as u64 >= total {
+                        return Ok(responses);
+                    }
+                }
+                HandlerOut::RequestFailed(failed_id, error) if failed_id == id => {
+                    // Keep the packets that did arrive if the remaining ones timed out
+                    if responses.is_empty() {
+                        return Err(error);
+                    }
+                    return Ok(responses);
+                }
+                event => self.handle(event),
+            }
+        }
+    }
+
+    /// Answers incoming requests until one matches the predicate or the timeout expires.
+    pub async fn wait_for_request(
+        &mut self,
+        timeout: Duration,
+        predicate: impl Fn(&NodeAddress, &Request) -> bool,
+    ) -> Option<Request> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let event = tokio::time::timeout_at(deadline, self.handler_recv.recv())
+                .await
+                .ok()??;
+            self.handle(event);
+            if let Some((address, request)) = self.received.last() {
+                if predicate(address, request) {
+                    return Some(request.clone());
+                }
+            }
+        }
+    }
+
+    /// Handles an event that is not a response to one of our requests.
+    fn handle(&mut self, event: HandlerOut) {
+        match event {
+            // We are not aware of any other nodes, let unknown nodes send their ENR
+            HandlerOut::WhoAreYou(whoareyou) => {
+                let _ = self
+                    .handler_send
+                    .send(HandlerIn::WhoAreYou(whoareyou, None));
+            }
+            HandlerOut::Request(address, request) => {
+                let body = match &request.body {
+                    RequestBody::Ping { .. } => ResponseBody::Pong {
+                        enr_seq: self.enr.read().seq(),
+                        ip: address.socket_addr.ip(),
+                        port: NonZeroU16::new(address.socket_addr.port())
+                            .unwrap_or(NonZeroU16::MIN),
+                    },
+                    RequestBody::FindNode { distances } => ResponseBody::Nodes {
+                        total: 1,
+                        nodes: if distances.contains(&0) {
+                            vec![self.enr()]
+                        } else {
+                            Vec::new()
+                        },
+                    },
+                    RequestBody::Talk { .. } => ResponseBody::Talk {
+                        response: Vec::new(),
+                    },
+                };
+                let response = Response {
+                    id: request.id.clone(),
+                    body,
+                };
+                let _ = self
+                    .handler_send
+                    .send(HandlerIn::Response(address.clone(), Box::new(response)));
+                self.received.push((address, *request));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for RawNode {
+    fn drop(&mut self) {
+        if let Some(exit) = self.exit.take() {
+            let _ = exit.send(());
+        }
+    }
+}
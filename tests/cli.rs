@@ -0,0 +1,128 @@
+use clap::CommandFactory;
+use discv5_cli::{
+    cli::{Cli, Subcommand},
+    server::{config, ServerSubcommand},
+};
+
+#[test]
+pub fn test_cli_definition() {
+    Cli::command().debug_assert();
+}
+
+#[test]
+pub fn test_server_config_file() {
+    let dir = std::env::temp_dir();
+    let toml = dir.join(format!("discv5-cli-config-{}.toml", std::process::id()));
+    std::fs::write(
+        &toml,
+        "listen-addresses = \"127.0.0.1\"\nlisten_port = 9200\nstatic-key = true\ntalk = [\"echo=echo\", \"*=static:01\"]\n",
+    )
+    .unwrap();
+    let toml = toml.to_string_lossy().to_string();
+
+    // Command line flags override the file
+    let cli =
+        Cli::try_parse_with_config(["discv5-cli", "server", "-c", &toml, "-p", "9201", "events"])
+            .unwrap();
+    let Some(Subcommand::Server(server)) = cli.subcommand else {
+        panic!("server command expected");
+    };
+    assert_eq!(server.listen_addresses, "127.0.0.1");
+    assert_eq!(server.listen_port, 9201);
+    assert!(server.static_key);
+    assert_eq!(server.talk, vec!["echo=echo", "*=static:01"]);
+    assert_eq!(server.break_time, 10);
+    assert_eq!(server.service, ServerSubcommand::Events);
+
+    // The effective configuration reads back into the same options
+    let printed = config::to_string(&server, config::ConfigFormat::Yaml).unwrap();
+    let yaml = dir.join(format!("discv5-cli-config-{}.yaml", std::process::id()));
+    std::fs::write(&yaml, printed).unwrap();
+    let yaml = yaml.to_string_lossy().to_string();
+    let cli = Cli::try_parse_with_config(["discv5-cli", "server", "-c", &yaml, "events"]).unwrap();
+    let Some(Subcommand::Server(reloaded)) = cli.subcommand else {
+        panic!("server command expected");
+    };
+    assert_eq!(reloaded.listen_port, 9201);
+    assert_eq!(reloaded.talk, server.talk);
+
+    let invalid = dir.join(format!(
+        "discv5-cli-config-invalid-{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(&invalid, "unknown-option = 1\n").unwrap();
+    let invalid = invalid.to_string_lossy().to_string();
+    assert!(
+        Cli::try_parse_with_config(["discv5-cli", "server", "-c", &invalid, "events"]).is_err()
+    );
+
+    for file in [toml, yaml, invalid] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
+
+#[test]
+pub fn test_server_config_service() {
+    let dir = std::env::temp_dir();
+    let toml = dir.join(format!("discv5-cli-service-{}.toml", std::process::id()));
+    std::fs::write(
+        &toml,
+        "no-search = true\n[service.crawl]\noutput = \"nodes.json\"\nparallelism = 4\n",
+    )
+    .unwrap();
+    let toml = toml.to_string_lossy().to_string();
+
+    // The file selects the service and its options
+    let cli = Cli::try_parse_with_config(["discv5-cli", "server", "-c", &toml]).unwrap();
+    let Some(Subcommand::Server(server)) = cli.subcommand else {
+        panic!("server command expected");
+    };
+    assert!(server.no_search);
+    let ServerSubcommand::Crawl(crawl) = server.service else {
+        panic!("crawl service expected");
+    };
+    assert_eq!(crawl.output.as_deref(), Some("nodes.json"));
+    assert_eq!(crawl.parallelism, 4);
+
+    // A service and bool options on the command line override the file
+    let cli =
+        Cli::try_parse_with_config(["discv5-cli", "server", "-c", &toml, "-x=false", "events"])
+            .unwrap();
+    let Some(Subcommand::Server(server)) = cli.subcommand else {
+        panic!("server command expected");
+    };
+    assert!(!server.no_search);
+    assert_eq!(server.service, ServerSubcommand::Events);
+
+    let name = dir.join(format!(
+        "discv5-cli-service-name-{}.yaml",
+        std::process::id()
+    ));
+    std::fs::write(&name, "service: events\n").unwrap();
+    let name = name.to_string_lossy().to_string();
+    let cli = Cli::try_parse_with_config(["discv5-cli", "server", "-c", &name]).unwrap();
+    let Some(Subcommand::Server(server)) = cli.subcommand else {
+        panic!("server command expected");
+    };
+    assert_eq!(server.service, ServerSubcommand::Events);
+
+    // Without a service anywhere, or with an unknown one, parsing fails
+    let none = dir.join(format!(
+        "discv5-cli-service-none-{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(&none, "listen-port = 9000\n").unwrap();
+    let none = none.to_string_lossy().to_string();
+    assert!(Cli::try_parse_with_config(["discv5-cli", "server", "-c", &none]).is_err());
+    let unknown = dir.join(format!(
+        "discv5-cli-service-unknown-{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(&unknown, "[service.crawl]\nunknown-option = 1\n").unwrap();
+    let unknown = unknown.to_string_lossy().to_string();
+    assert!(Cli::try_parse_with_config(["discv5-cli", "server", "-c", &unknown]).is_err());
+
+    for file in [toml, name, none, unknown] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::{
+    net::{IpAddr, Ipv4Addr, UdpSocket},
+    time::Duration,
+};
+
+use discv5::{enr, ConfigBuilder, Discv5, Key, ListenConfig};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// Returns a UDP port on the address that the OS considers free, by binding to port 0. Tests use
+/// it instead of fixed ports so they can run in parallel with other test runs.
+pub fn free_port(ip: impl Into<IpAddr>) -> u16 {
+    UdpSocket::bind((ip.into(), 0))
+        .and_then(|socket| socket.local_addr())
+        .expect("A free UDP port")
+        .port()
+}
+
+/// Returns a free UDP port on the ipv4 loopback address.
+pub fn free_port_v4() -> u16 {
+    free_port(Ipv4Addr::LOCALHOST)
+}
+
+/// Starts a local discv5 server listening on a free port of the loopback address.
+pub async fn local_discv5() -> Discv5 {
+    local_discv5_with(|_| {}).await
+}
+
+/// Starts a local discv5 server with a custom configuration.
+pub async fn local_discv5_with(configure: impl FnOnce(&mut ConfigBuilder)) -> Discv5 {
+    let port = free_port_v4();
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let enr = enr::Enr::builder()
+        .ip4(Ipv4Addr::LOCALHOST)
+        .udp4(port)
+        .build(&enr_key)
+        .unwrap();
+    let mut config = ConfigBuilder::new(ListenConfig::from_ip(Ipv4Addr::LOCALHOST.into(), port));
+    configure(&mut config);
+    let mut discv5 = Discv5::new(enr, enr_key, config.build()).unwrap();
+    discv5.start().await.unwrap();
+    discv5
+}
+
+/// Fills the furthest bucket of the server's routing table with `count` ENRs. Sixteen ENRs do not
+/// fit into a single NODES packet.
+pub fn fill_furthest_bucket(discv5: &Discv5, count: u16) {
+    let local_key = Key::from(discv5.local_enr().node_id());
+    let mut added = 0;
+    while added < count {
+        let enr = enr::Enr::builder()
+            .ip4(Ipv4Addr::new(10, 0, 0, 1))
+            .udp4(9000 + added)
+            .build(&enr::CombinedKey::generate_secp256k1())
+            .unwrap();
+        if local_key.log2_distance(&Key::from(enr.node_id())) == Some(256) {
+            discv5.add_enr(enr).unwrap();
+            added += 1;
+        }
+    }
+}
+
+/// Generates an ENR on the loopback address with the fields set by `fields`, in the furthest
+/// bucket of `peer` if given, which is the first bucket a crawl requests.
+pub fn loopback_enr(
+    port: u16,
+    peer: Option<&Enr>,
+    fields: impl Fn(&mut enr::Builder<enr::CombinedKey>),
+) -> (Enr, enr::CombinedKey) {
+    loop {
+        let enr_key = enr::CombinedKey::generate_secp256k1();
+        let mut builder = enr::Enr::builder();
+        builder.ip4(Ipv4Addr::LOCALHOST).udp4(port);
+        fields(&mut builder);
+        let enr = builder.build(&enr_key).unwrap();
+        let distance =
+            peer.map(|peer| Key::from(peer.node_id()).log2_distance(&Key::from(enr.node_id())));
+        if distance.is_none() || distance == Some(Some(256)) {
+            return (enr, enr_key);
+        }
+    }
+}
+
+/// Starts a local discv5 server on a free port of the loopback address with the ENR of
+/// [`loopback_enr`]. Requests time out quickly and are not retried.
+pub async fn loopback_discv5(
+    peer: Option<&Enr>,
+    fields: impl Fn(&mut enr::Builder<enr::CombinedKey>),
+) -> Discv5 {
+    let port = free_port_v4();
+    let (enr, enr_key) = loopback_enr(port, peer, fields);
+    let config = ConfigBuilder::new(ListenConfig::from_ip(Ipv4Addr::LOCALHOST.into(), port))
+        .request_timeout(Duration::from_millis(500))
+        .request_retries(0)
+        .build();
+    let mut discv5 = Discv5::new(enr, enr_key, config).unwrap();
+    discv5.start().await.unwrap();
+    discv5
+}
@@ -0,0 +1,91 @@
+use std::{
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+
+use discv5_cli::{
+    conformance::{self, Outcome},
+    request_enr::target::Target,
+    utils::local_node::LocalNode,
+};
+
+mod common;
+use common::{fill_furthest_bucket, free_port_v4, local_discv5};
+
+/// The peer id of the server's static key.
+const STATIC_PEER_ID: &str = "16Uiu2HAmSH2XVgZqYHWucap5kuPzLnt2TsNQkoppVxB5eJGvaXwm";
+
+/// Kills the server once the test is done.
+struct ServerProcess(Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+/// The options of the node the checks are run from.
+fn local_node() -> LocalNode {
+    LocalNode {
+        listen_addresses: "127.0.0.1".into(),
+        listen_port: free_port_v4(),
+        listen_port_v6: None,
+    }
+}
+
+#[tokio::test]
+pub async fn test_conformance_against_server() {
+    let port = free_port_v4().to_string();
+    let server = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args([
+            "server",
+            "-l",
+            "127.0.0.1",
+            "-p",
+            &port,
+            "-w",
+            "-k",
+            "-s",
+            "0",
+            "events",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let _server = ServerProcess(server);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let target = format!("/ip4/127.0.0.1/udp/{port}/p2p/{STATIC_PEER_ID}")
+        .parse::<Target>()
+        .unwrap();
+    let report = conformance::run_checks(&local_node(), &target, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    assert!(report.passed(), "{:?}", report);
+    assert_eq!(report.results.len(), 8);
+    // The server starts with an empty routing table
+    assert_eq!(
+        report.get("multi-packet-nodes").unwrap().outcome,
+        Outcome::Skip
+    );
+}
+
+#[tokio::test]
+pub async fn test_conformance_multi_packet_nodes() {
+    let remote = local_discv5().await;
+    // Fill the furthest bucket with more ENRs than fit into a single packet
+    fill_furthest_bucket(&remote, 16);
+
+    let target = Target::Enr(remote.local_enr());
+    let report = conformance::run_checks(&local_node(), &target, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    assert!(report.passed(), "{:?}", report);
+    assert_eq!(
+        report.get("multi-packet-nodes").unwrap().outcome,
+        Outcome::Pass
+    );
+}
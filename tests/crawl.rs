@@ -0,0 +1,178 @@
+use std::{
+    net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6},
+    sync::Arc,
+};
+
+use discv5::{enr, ConfigBuilder, Discv5, ListenConfig};
+use discv5_cli::{
+    server::{
+        command::Crawl,
+        services::{crawl, metrics::Metrics},
+    },
+    utils::local_node::LocalNode,
+};
+
+mod common;
+use common::{free_port, free_port_v4, loopback_discv5, loopback_enr};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// The options of the nodes the crawl sends its requests from.
+fn loopback_node() -> LocalNode {
+    LocalNode {
+        listen_addresses: "127.0.0.1".to_string(),
+        listen_port: 0,
+        listen_port_v6: None,
+    }
+}
+
+#[tokio::test]
+pub async fn test_crawl() {
+    // A chain of nodes that only know the next one, ending in a node that does not answer
+    let a = loopback_discv5(None, |_| {}).await;
+    let b = loopback_discv5(Some(&a.local_enr()), |_| {}).await;
+    let c = loopback_discv5(Some(&b.local_enr()), |_| {}).await;
+    let (unresponsive, _) = loopback_enr(free_port_v4(), Some(&c.local_enr()), |_| {});
+    a.add_enr(b.local_enr()).unwrap();
+    b.add_enr(c.local_enr()).unwrap();
+    c.add_enr(unresponsive.clone()).unwrap();
+
+    let crawler = loopback_discv5(None, |_| {}).await;
+    crawler.add_enr(a.local_enr()).unwrap();
+    let settings = Crawl {
+        parallelism: 4,
+        distances_per_request: 1,
+        empty_distances: 1,
+        ..Default::default()
+    };
+    let crawler = Arc::new(crawler);
+    let metrics = Metrics::new(Arc::clone(&crawler));
+    let dataset = crawl::crawl(crawler, &loopback_node(), &settings, Some(&metrics))
+        .await
+        .unwrap();
+
+    let node = |enr: &Enr| {
+        let node_id = hex::encode(enr.node_id().raw());
+        dataset
+            .nodes
+            .iter()
+            .find(|node| node.node_id == node_id)
+            .unwrap_or_else(|| panic!("{} should be discovered", node_id))
+            .clone()
+    };
+    assert_eq!(dataset.nodes.len(), 4);
+    assert!(dataset.started_at <= dataset.finished_at);
+
+    let seed = node(&a.local_enr());
+    assert!(seed.found_by.is_empty());
+    assert!(seed.responsive);
+    assert!(seed.returned >= 1);
+
+    for (enr, peer) in [(b.local_enr(), &a), (c.local_enr(), &b)] {
+        let node = node(&enr);
+        assert!(node.responsive);
+        assert!(node.requests > 0);
+        assert!(node
+            .found_by
+            .contains(&hex::encode(peer.local_enr().node_id().raw())));
+        assert!(node.discovered_at >= seed.discovered_at);
+    }
+
+    let unresponsive = node(&unresponsive);
+    assert!(!unresponsive.responsive);
+    assert_eq!(unresponsive.failure.as_deref(), Some("no-response"));
+
+    // Every visited node is recorded as a query of the crawl
+    let encoded = metrics.encode().unwrap();
+    for line in [
+        "discv5_queries_total{source=\"crawl\",result=\"success\"} 3",
+        "discv5_queries_total{source=\"crawl\",result=\"failure\"} 1",
+        "discv5_query_duration_seconds_count{source=\"crawl\"} 4",
+    ] {
+        assert!(encoded.contains(line), "{} missing from\n{}", line, encoded);
+    }
+    assert_eq!(unresponsive.requests, 1);
+    assert_eq!(
+        unresponsive.found_by,
+        vec![hex::encode(c.local_enr().node_id().raw())]
+    );
+}
+
+#[tokio::test]
+pub async fn test_crawl_multiple_packets() {
+    // A full bucket takes more than one NODES packet
+    let peer = loopback_discv5(None, |_| {}).await;
+    let bucket: Vec<Enr> = (0..16)
+        .map(|_| loopback_enr(free_port_v4(), Some(&peer.local_enr()), |_| {}).0)
+        .collect();
+    for enr in &bucket {
+        peer.add_enr(enr.clone()).unwrap();
+    }
+
+    let crawler = loopback_discv5(None, |_| {}).await;
+    crawler.add_enr(peer.local_enr()).unwrap();
+    let settings = Crawl {
+        parallelism: 16,
+        distances_per_request: 16,
+        ..Default::default()
+    };
+    let dataset = crawl::crawl(Arc::new(crawler), &loopback_node(), &settings, None)
+        .await
+        .unwrap();
+
+    let peer_id = hex::encode(peer.local_enr().node_id().raw());
+    let peer = dataset
+        .nodes
+        .iter()
+        .find(|node| node.node_id == peer_id)
+        .unwrap();
+    assert!(peer.responsive);
+    assert_eq!(peer.returned, bucket.len());
+    assert_eq!(peer.requests, 16);
+    for enr in &bucket {
+        let node_id = hex::encode(enr.node_id().raw());
+        assert!(dataset.nodes.iter().any(|node| node.node_id == node_id));
+    }
+}
+
+#[tokio::test]
+pub async fn test_crawl_ipv6_nodes() {
+    // A node that only advertises an ipv6 socket is contacted even from ipv4 listening addresses
+    let port = free_port(Ipv6Addr::LOCALHOST);
+    let ipv6_only = enr::Enr::builder()
+        .ip6(Ipv6Addr::LOCALHOST)
+        .udp6(port)
+        .build(&enr::CombinedKey::generate_secp256k1())
+        .unwrap();
+    // Only a dual stack routing table takes the ipv6 node to start the crawl from
+    let listen_config = ListenConfig::from_two_sockets(
+        Some(SocketAddrV4::new(Ipv4Addr::LOCALHOST, free_port_v4())),
+        Some(SocketAddrV6::new(
+            Ipv6Addr::LOCALHOST,
+            free_port(Ipv6Addr::LOCALHOST),
+            0,
+            0,
+        )),
+    );
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let local_enr = enr::Enr::builder().build(&enr_key).unwrap();
+    let mut crawler = Discv5::new(
+        local_enr,
+        enr_key,
+        ConfigBuilder::new(listen_config).build(),
+    )
+    .unwrap();
+    crawler.start().await.unwrap();
+    crawler.add_enr(ipv6_only.clone()).unwrap();
+    let settings = Crawl {
+        parallelism: 1,
+        ..Default::default()
+    };
+    let dataset = crawl::crawl(Arc::new(crawler), &loopback_node(), &settings, None)
+        .await
+        .unwrap();
+
+    assert_eq!(dataset.nodes.len(), 1);
+    assert_eq!(dataset.nodes[0].failure.as_deref(), Some("no-response"));
+    assert_eq!(dataset.nodes[0].requests, 1);
+}
@@ -0,0 +1,102 @@
+use discv5::{ConfigBuilder, Discv5, ListenConfig};
+use discv5_cli::server::{bootstrap, command, data_dir, enr_build, keys};
+
+#[test]
+pub fn test_enr_seq_persistence() -> std::result::Result<(), eyre::Report> {
+    let dir = std::env::temp_dir().join(format!("discv5-cli-data-dir-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut server = command::Server {
+        listen_addresses: "0.0.0.0".to_string(),
+        listen_port: 9000,
+        enr_addresses: Some("45.31.182.164".to_string()),
+        data_dir: Some(dir.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+
+    // The first run generates and stores a key
+    let enr_key = keys::generate(&server)?;
+    let mut enr = enr_build::build(&server, &enr_key)?;
+    let previous = data_dir::load_enr(&dir)?;
+    assert!(previous.is_none());
+    enr_build::reconcile_seq(&server, &mut enr, &enr_key, previous.as_ref())?;
+    data_dir::save_enr(&dir, &enr)?;
+    let first_seq = enr.seq();
+
+    // The key is only readable by the owner
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::metadata(dir.join(data_dir::KEY_FILENAME))?;
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    }
+    assert!(!dir.join("key.partial").exists());
+
+    // An unchanged record keeps its sequence number and identity
+    let enr_key = keys::generate(&server)?;
+    let mut enr = enr_build::build(&server, &enr_key)?;
+    let previous = data_dir::load_enr(&dir)?;
+    assert_eq!(previous.as_ref().map(|e| e.node_id()), Some(enr.node_id()));
+    enr_build::reconcile_seq(&server, &mut enr, &enr_key, previous.as_ref())?;
+    assert_eq!(enr.seq(), first_seq);
+
+    // A changed record bumps the sequence number
+    server.listen_port = 9001;
+    let mut enr = enr_build::build(&server, &enr_key)?;
+    enr_build::reconcile_seq(&server, &mut enr, &enr_key, previous.as_ref())?;
+    assert_eq!(enr.seq(), first_seq + 1);
+    assert_eq!(enr.udp4(), Some(9001));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_table_persistence() -> std::result::Result<(), eyre::Report> {
+    let dir = std::env::temp_dir().join(format!("discv5-cli-table-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let new_discv5 = || -> eyre::Result<Discv5> {
+        let server = command::Server {
+            listen_addresses: "0.0.0.0".to_string(),
+            listen_port: 9000,
+            ..Default::default()
+        };
+        let enr_key = keys::generate(&server)?;
+        let enr = enr_build::build(&server, &enr_key)?;
+        let config = ConfigBuilder::new(ListenConfig::default()).build();
+        Discv5::new(enr, enr_key, config).map_err(|e| eyre::eyre!(e))
+    };
+
+    assert!(data_dir::load_table(&dir)?.is_none());
+
+    // Store the table bootstrapped from the example file
+    let mut discv5 = new_discv5()?;
+    bootstrap::boostrap(&mut discv5, Some("example.bootstrap.json".to_string())).await?;
+    let table = bootstrap::BootstrapStore::from_table(&discv5);
+    assert!(!table.data.is_empty());
+    assert!(table
+        .data
+        .iter()
+        .all(|node| node.state == bootstrap::State::Disconnected));
+    data_dir::save_table(&dir, &table)?;
+
+    // A new server restores the same entries
+    let restored = data_dir::load_table(&dir)?.expect("The table should be stored");
+    assert_eq!(restored, table);
+    let discv5 = new_discv5()?;
+    assert_eq!(restored.add_to_table(&discv5), table.data.len());
+    let mut expected = table
+        .data
+        .iter()
+        .map(|node| node.enr.parse::<discv5::Enr>().map(|enr| enr.node_id()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| eyre::eyre!(e))?;
+    let mut entries = discv5.table_entries_id();
+    expected.sort_by_key(|node_id| node_id.raw());
+    entries.sort_by_key(|node_id| node_id.raw());
+    assert_eq!(entries, expected);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::process::Command;
+
+use discv5::enr::{CombinedKey, Enr, NodeId};
+use discv5_cli::{distance::NodeDistance, utils::enr_ext::EnrExt, utils::node_id};
+
+#[test]
+pub fn test_node_distance() {
+    let zero = NodeId::new(&[0u8; 32]);
+    let mut raw = [0u8; 32];
+    raw[1] = 0b0010_0000;
+    let other = NodeId::new(&raw);
+
+    let distance = NodeDistance::between(&zero, &other);
+    assert_eq!(distance.xor, raw);
+    assert_eq!(distance.log2, Some(246));
+    assert_eq!(distance.shared_prefix, 10);
+
+    let same = NodeDistance::between(&other, &other);
+    assert_eq!(same.log2, None);
+    assert_eq!(same.shared_prefix, 256);
+}
+
+#[test]
+pub fn test_node_id_parsing() -> std::result::Result<(), eyre::Report> {
+    let key = CombinedKey::generate_secp256k1();
+    let enr = Enr::builder().build(&key)?;
+    let expected = enr.node_id();
+
+    assert_eq!(node_id::parse(&enr.to_base64())?, expected);
+    assert_eq!(node_id::parse(&hex::encode(expected.raw()))?, expected);
+    assert_eq!(node_id::parse(&enr.peer_id().to_string())?, expected);
+    assert_eq!(
+        node_id::parse(&format!("key:{}", hex::encode(key.encode())))?,
+        expected
+    );
+    assert!(node_id::parse("not a node").is_err());
+    Ok(())
+}
+
+#[test]
+pub fn test_distance_output() -> std::result::Result<(), eyre::Report> {
+    let output = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args(["-v", "error", "distance", "not-a-node", "not-a-node"])
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+
+    let zero = hex::encode([0u8; 32]);
+    let mut raw = [0u8; 32];
+    raw[1] = 0b0010_0000;
+    let output = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args(["-v", "error", "distance", &zero, &hex::encode(raw)])
+        .output()?;
+    assert!(output.status.success());
+    // The results are written to stdout regardless of the log level
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("Log2 distance (bucket): 246"));
+    assert!(stdout.contains("Shared prefix length: 10"));
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use discv5::enr;
+use discv5_cli::{
+    server::{command, enr_build},
+    utils::eth2::EnrForkId,
+};
+
+#[test]
+pub fn test_enr_eth2() -> std::result::Result<(), eyre::Report> {
+    let server = command::Server {
+        listen_addresses: "0.0.0.0".to_string(),
+        listen_port: 9000,
+        enr_eth2: Some("6a95a1a9040000000000000000000000".to_string()),
+        ..Default::default()
+    };
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let enr = enr_build::build(&server, &enr_key)?;
+
+    // The field is a byte string, not a list of bytes
+    let mut expected = vec![0x80 + 16];
+    expected.extend(hex::decode("6a95a1a9040000000000000000000000")?);
+    assert_eq!(enr.get_raw_rlp("eth2"), Some(expected.as_slice()));
+    let fork_id = EnrForkId::from_enr(&enr).expect("An eth2 field");
+    assert_eq!(
+        fork_id.map(|id| id.fork_digest),
+        Ok([0x6a, 0x95, 0xa1, 0xa9])
+    );
+    Ok(())
+}
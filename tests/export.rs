@@ -0,0 +1,223 @@
+use std::net::Ipv4Addr;
+
+use clap::Parser;
+use discv5::{enr, ConfigBuilder, Discv5, ListenConfig};
+use discv5_cli::{
+    cli::Cli,
+    server::{
+        bootstrap,
+        services::{
+            crawl::{CrawlDataset, CrawledNode},
+            export::{self, DiscoveredNode, Discoveries, ExportFormat, Source},
+            graph,
+        },
+    },
+};
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+fn local_enr(port: u16, eth2: Option<&[u8]>) -> Enr {
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let mut builder = enr::Enr::builder();
+    builder.ip4(Ipv4Addr::LOCALHOST).udp4(port);
+    if let Some(eth2) = eth2 {
+        builder.add_value("eth2", &eth2);
+    }
+    builder.build(&enr_key).unwrap()
+}
+
+/// A node of a crawl dataset, returned by the `found_by` nodes.
+fn crawled(enr: &Enr, responsive: bool, found_by: &[&Enr]) -> CrawledNode {
+    CrawledNode {
+        node_id: hex::encode(enr.node_id().raw()),
+        enr: enr.to_base64(),
+        discovered_at: 1,
+        responsive,
+        failure: (!responsive).then(|| "no-response".to_string()),
+        requests: 1,
+        returned: 0,
+        found_by: found_by
+            .iter()
+            .map(|peer| hex::encode(peer.node_id().raw()))
+            .collect(),
+    }
+}
+
+#[tokio::test]
+pub async fn test_export_formats() -> std::result::Result<(), eyre::Report> {
+    let dir = std::env::temp_dir().join(format!("discv5-cli-export-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+
+    let fork_id = hex::decode("6a95a1a9040000000000000000000000")?;
+    let queried = local_enr(9000, Some(&fork_id));
+    let responsive = local_enr(9001, None);
+    let unresponsive = local_enr(9002, None);
+
+    let discoveries = Discoveries::new(100);
+    discoveries.record(&queried, Source::Query);
+    discoveries.record_crawl(&CrawlDataset {
+        started_at: 0,
+        finished_at: 2,
+        nodes: vec![
+            crawled(&queried, true, &[&responsive]),
+            crawled(&responsive, true, &[&queried]),
+            crawled(&unresponsive, false, &[&responsive]),
+        ],
+    });
+
+    let nodes = discoveries.nodes();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(nodes[0].source, Source::Query);
+    assert_eq!(nodes[0].first_seen, 1);
+    assert_eq!(nodes[0].fork_digest.as_deref(), Some("0x6a95a1a9"));
+    assert_eq!(nodes[0].responsive, Some(true));
+    assert_eq!(nodes[1].source, Source::Crawl);
+    assert_eq!(nodes[2].responsive, Some(false));
+
+    // CSV has a header and a row per node
+    let path = dir.join("nodes.csv");
+    assert_eq!(ExportFormat::from_path(&path), Ok(ExportFormat::Csv));
+    export::write(&path, ExportFormat::Csv, &nodes)?;
+    let csv = std::fs::read_to_string(&path)?;
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("node_id,peer_id,seq,ip4,udp4"));
+    assert!(lines[1].contains(",0x6a95a1a9,0x04000000,0,query,"));
+    assert!(lines[3].contains(",false,"));
+
+    // JSON lines read back into the same nodes
+    let path = dir.join("nodes.jsonl");
+    assert_eq!(ExportFormat::from_path(&path), Ok(ExportFormat::Jsonl));
+    export::write(&path, ExportFormat::Jsonl, &nodes)?;
+    let jsonl = std::fs::read_to_string(&path)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<DiscoveredNode>, _>>()?;
+    assert_eq!(jsonl, nodes);
+
+    // The bootstrap file leaves out the unresponsive node and bootstraps a server
+    let path = dir.join("bootstrap.json");
+    assert_eq!(ExportFormat::from_path(&path), Ok(ExportFormat::Bootstrap));
+    export::write(&path, ExportFormat::Bootstrap, &nodes)?;
+    let config = ConfigBuilder::new(ListenConfig::default()).build();
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let local_enr = enr::Enr::builder().build(&enr_key).unwrap();
+    let mut discv5 = Discv5::new(local_enr, enr_key, config).unwrap();
+    bootstrap::boostrap(&mut discv5, Some(path.to_string_lossy().to_string())).await?;
+    let table = discv5.table_entries_id();
+    assert_eq!(table.len(), 2);
+    assert!(table.contains(&queried.node_id()));
+    assert!(table.contains(&responsive.node_id()));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+pub fn test_peer_graph() {
+    let fork_id = hex::decode("6a95a1a9040000000000000000000000").unwrap();
+    let seed = local_enr(9000, Some(&fork_id));
+    let returned = local_enr(9001, None);
+    let unresponsive = local_enr(9002, None);
+    let id = |enr: &Enr| hex::encode(enr.node_id().raw());
+
+    let discoveries = Discoveries::new(100);
+    discoveries.record(&seed, Source::Query);
+    discoveries.record_crawl(&CrawlDataset {
+        started_at: 0,
+        finished_at: 2,
+        nodes: vec![
+            crawled(&seed, true, &[]),
+            crawled(&returned, true, &[&seed]),
+            crawled(&unresponsive, false, &[&seed, &returned]),
+        ],
+    });
+    let nodes = discoveries.nodes();
+
+    let dot = graph::to_dot(&nodes);
+    assert!(dot.starts_with("digraph discv5 {"));
+    assert!(dot.contains(&format!(
+        "\"{}\" [label=\"{}\", fork_digest=\"0x6a95a1a9\", ip_family=\"ipv4\", responsiveness=\"responsive\", style=solid];",
+        id(&seed),
+        &id(&seed)[..8]
+    )));
+    assert!(dot.contains("responsiveness=\"unresponsive\", style=dashed"));
+    for (from, to) in [
+        (&seed, &returned),
+        (&seed, &unresponsive),
+        (&returned, &unresponsive),
+    ] {
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\";", id(from), id(to))));
+    }
+    assert_eq!(dot.matches(" -> ").count(), 3);
+
+    let graphml = graph::to_graphml(&nodes);
+    assert!(graphml.contains("<graph id=\"discv5\" edgedefault=\"directed\">"));
+    assert_eq!(graphml.matches("<node id=").count(), 3);
+    assert_eq!(graphml.matches("<edge ").count(), 3);
+    assert!(graphml.contains("<data key=\"fork_digest\">0x6a95a1a9</data>"));
+    assert!(graphml.contains(&format!(
+        "<edge source=\"{}\" target=\"{}\"/>",
+        id(&returned),
+        id(&unresponsive)
+    )));
+    assert_eq!(
+        ExportFormat::from_path(std::path::Path::new("peers.graphml")),
+        Ok(ExportFormat::GraphMl)
+    );
+    assert_eq!(
+        ExportFormat::from_path(std::path::Path::new("peers.dot")),
+        Ok(ExportFormat::Dot)
+    );
+}
+
+#[test]
+pub fn test_export_limits() {
+    // Files of an unknown format are rejected when parsing the options
+    assert!(ExportFormat::from_path(std::path::Path::new("nodes.txt")).is_err());
+    assert!(ExportFormat::from_path(std::path::Path::new("nodes")).is_err());
+    assert!(
+        Cli::try_parse_from(["discv5-cli", "server", "--export", "nodes.txt", "events"]).is_err()
+    );
+    assert!(
+        Cli::try_parse_from(["discv5-cli", "server", "--export", "nodes.json", "events"]).is_ok()
+    );
+
+    // Peer graph exports make the query service record who returned whom
+    assert!(export::exports_peer_graph(&[
+        "nodes.csv".to_string(),
+        "peers.graphml".to_string()
+    ]));
+    assert!(!export::exports_peer_graph(&["nodes.csv".to_string()]));
+
+    // Beyond the maximum, the least recently seen nodes are dropped
+    let discoveries = Discoveries::new(10);
+    let enrs: Vec<Enr> = (0..11).map(|i| local_enr(9000 + i, None)).collect();
+    discoveries.record_crawl(&CrawlDataset {
+        started_at: 0,
+        finished_at: 12,
+        nodes: enrs
+            .iter()
+            .enumerate()
+            .map(|(i, enr)| CrawledNode {
+                discovered_at: i as u64 + 1,
+                ..crawled(enr, true, &[])
+            })
+            .collect(),
+    });
+    let nodes = discoveries.nodes();
+    assert_eq!(nodes.len(), 9);
+    assert_eq!(nodes[0].first_seen, 3);
+    assert_eq!(nodes[8].node_id, hex::encode(enrs[10].node_id().raw()));
+
+    // Nodes seen again are kept
+    discoveries.record(&enrs[2], Source::Query);
+    discoveries.record(&enrs[0], Source::Query);
+    discoveries.record(&enrs[1], Source::Query);
+    let nodes = discoveries.nodes();
+    assert!(nodes.len() <= 10);
+    assert!(nodes
+        .iter()
+        .any(|node| node.node_id == hex::encode(enrs[1].node_id().raw())));
+}
@@ -0,0 +1,39 @@
+use std::process::Command;
+
+use discv5_cli::{findnode, request_enr::target::Target, utils::local_node::LocalNode};
+
+mod common;
+use common::{fill_furthest_bucket, free_port_v4, local_discv5};
+
+#[tokio::test]
+pub async fn test_find_node_collects_all_packets() {
+    let remote = local_discv5().await;
+
+    // Fill the furthest bucket of the remote with more ENRs than fit into a single packet
+    fill_furthest_bucket(&remote, 16);
+
+    let local_node = LocalNode {
+        listen_addresses: "127.0.0.1".into(),
+        listen_port: free_port_v4(),
+        listen_port_v6: None,
+    };
+    let target = Target::Enr(remote.local_enr());
+    let response = findnode::find_node(&local_node, &target, vec![256, 0])
+        .await
+        .unwrap();
+
+    assert!(response.is_complete());
+    assert!(response.packets > 1);
+    assert_eq!(response.enrs.len(), 17);
+    assert!(response.enrs.contains(&remote.local_enr()));
+}
+
+#[test]
+pub fn test_find_node_error_exit_code() -> std::result::Result<(), eyre::Report> {
+    let status = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args(["findnode", "--target", "not-a-target", "--distances", "256"])
+        .output()?
+        .status;
+    assert_eq!(status.code(), Some(1));
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::{
+    net::Ipv4Addr,
+    sync::{Arc, OnceLock},
+};
+
+use discv5::{enr, ConfigBuilder, Discv5, ListenConfig};
+use discv5_cli::server::fork_digest::{self, ForkDigestFilter};
+
+/// The filter of the routing table, which discv5 only accepts as a plain function.
+static FILTER: OnceLock<Arc<ForkDigestFilter>> = OnceLock::new();
+
+fn enr_with(eth2: Option<&str>) -> enr::Enr<enr::CombinedKey> {
+    let mut builder = enr::Enr::builder();
+    builder.ip4(Ipv4Addr::LOCALHOST).udp4(9000);
+    let eth2 = eth2.map(|eth2| hex::decode(eth2).unwrap());
+    if let Some(eth2) = &eth2 {
+        builder.add_value("eth2", &eth2.as_slice());
+    }
+    builder
+        .build(&enr::CombinedKey::generate_secp256k1())
+        .unwrap()
+}
+
+#[test]
+pub fn test_fork_digest_filter() {
+    assert_eq!(
+        fork_digest::parse("0x6a95a1a9"),
+        Ok([0x6a, 0x95, 0xa1, 0xa9])
+    );
+    assert_eq!(fork_digest::parse("6a95a1a9"), Ok([0x6a, 0x95, 0xa1, 0xa9]));
+    assert!(fork_digest::parse("0x6a95a1").is_err());
+    assert!(fork_digest::parse("holesky").is_err());
+
+    let filter = Arc::new(ForkDigestFilter::new([0x6a, 0x95, 0xa1, 0xa9]));
+    let local = enr_with(Some("6a95a1a9040000000000000000000000"));
+    let foreign = enr_with(Some("bba4da96040000000000000000000000"));
+    let other_foreign = enr_with(Some("bba4da960400000000000000ffffffff"));
+    let without_eth2 = enr_with(None);
+    let invalid = enr_with(Some("6a95a1a9"));
+
+    assert!(filter.matches(&local));
+    assert!(!filter.matches(&foreign));
+    assert!(!filter.matches(&foreign));
+    assert!(!filter.matches(&other_foreign));
+    assert!(!filter.matches(&without_eth2));
+    assert!(!filter.matches(&invalid));
+
+    // Rejected nodes are counted once per fork digest
+    let rejected = filter.rejected();
+    assert_eq!(rejected.len(), 3);
+    assert_eq!(rejected["0xbba4da96"], 2);
+    assert_eq!(rejected["none"], 1);
+    assert_eq!(rejected["invalid"], 1);
+
+    // The routing table only keeps nodes of the network
+    FILTER.set(Arc::clone(&filter)).unwrap();
+    let table_filter: fn(&enr::Enr<enr::CombinedKey>) -> bool =
+        |enr| FILTER.get().is_some_and(|filter| filter.matches(enr));
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let local_enr = enr::Enr::builder().build(&enr_key).unwrap();
+    let config = ConfigBuilder::new(ListenConfig::default())
+        .table_filter(table_filter)
+        .build();
+    let discv5: Discv5 = Discv5::new(local_enr, enr_key, config).unwrap();
+    assert!(discv5.add_enr(local).is_ok());
+    let gnosis = enr_with(Some("56fdb5e0040000000000000000000000"));
+    assert!(discv5.add_enr(gnosis).is_err());
+    assert_eq!(discv5.table_entries_id().len(), 1);
+    assert_eq!(filter.rejected()["0x56fdb5e0"], 1);
+}
+
+#[test]
+pub fn test_fork_digest_filter_bounds() {
+    // Beyond 256 foreign fork digests, nodes are counted as other
+    let filter = ForkDigestFilter::new([0x6a, 0x95, 0xa1, 0xa9]);
+    for digest in 0..300u32 {
+        let eth2 = format!(
+            "{}0400000000000000ffffffff",
+            hex::encode(digest.to_be_bytes())
+        );
+        assert!(!filter.matches(&enr_with(Some(&eth2))));
+    }
+    let rejected = filter.rejected();
+    assert_eq!(rejected.len(), 257);
+    assert_eq!(rejected["other"], 44);
+    assert_eq!(rejected.values().sum::<usize>(), 300);
+}
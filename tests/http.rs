@@ -0,0 +1,168 @@
+use std::{net::Ipv4Addr, sync::Arc};
+
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use discv5::{enr, Discv5};
+use discv5_cli::{
+    request_enr::output::EnrDetails,
+    server::{
+        fork_digest::ForkDigestFilter,
+        services::{
+            http::{self, Peers, TableEntry},
+            metrics::Metrics,
+        },
+    },
+};
+use serde::de::DeserializeOwned;
+use tower::ServiceExt;
+
+/// Sends a request to the API and decodes the JSON response.
+async fn call<T: DeserializeOwned>(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, Option<T>) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).ok())
+}
+
+#[tokio::test]
+pub async fn test_http_api() {
+    let discv5 = common::loopback_discv5(None, |_| {}).await;
+    let router = http::router(Arc::new(discv5), None, None);
+
+    let (status, enr) = call::<EnrDetails>(&router, "GET", "/enr", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(enr.unwrap().seq, 1);
+
+    // Add a node to the routing table
+    let node = enr::Enr::builder()
+        .ip4(Ipv4Addr::new(10, 0, 0, 1))
+        .udp4(9000)
+        .build(&enr::CombinedKey::generate_secp256k1())
+        .unwrap();
+    let (status, _) = call::<TableEntry>(
+        &router,
+        "POST",
+        "/table",
+        Some(serde_json::json!({ "enr": "enr:invalid" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, entry) = call::<TableEntry>(
+        &router,
+        "POST",
+        "/table",
+        Some(serde_json::json!({ "enr": node.to_base64() })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let entry = entry.unwrap();
+    assert_eq!(entry.node_id, hex::encode(node.node_id().raw()));
+    assert_eq!(entry.state, "disconnected");
+
+    let (_, entries) = call::<Vec<TableEntry>>(&router, "GET", "/table", None).await;
+    assert_eq!(entries.unwrap().len(), 1);
+    let (_, peers) = call::<Peers>(&router, "GET", "/peers", None).await;
+    assert_eq!(
+        peers.unwrap(),
+        Peers {
+            connected: 0,
+            table: 1
+        }
+    );
+
+    // Updating the ENR increments its sequence number
+    let (status, enr) = call::<EnrDetails>(
+        &router,
+        "PATCH",
+        "/enr",
+        Some(serde_json::json!({ "test": "0x01" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let enr = enr.unwrap();
+    assert_eq!(enr.seq, 2);
+    assert_eq!(enr.keys.get("test").map(String::as_str), Some("0x01"));
+
+    // An invalid field leaves the ENR unchanged
+    let (status, _) = call::<EnrDetails>(
+        &router,
+        "PATCH",
+        "/enr",
+        Some(serde_json::json!({ "a": "0x02", "b": format!("0x{}", "00".repeat(300)) })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, enr) = call::<EnrDetails>(&router, "GET", "/enr", None).await;
+    let enr = enr.unwrap();
+    assert_eq!(enr.seq, 2);
+    assert!(!enr.keys.contains_key("a"));
+
+    let uri = format!("/table/{}", hex::encode(node.node_id().raw()));
+    let (status, _) = call::<()>(&router, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = call::<()>(&router, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Starts a local discv5 server on the loopback address with an eth2 field of the fork digest.
+async fn fork_discv5(fork_digest: &str) -> Discv5 {
+    let eth2 = hex::decode(format!("{fork_digest}0400000000000000ffffffff")).unwrap();
+    common::loopback_discv5(None, |builder| {
+        builder.add_value("eth2", &eth2.as_slice());
+    })
+    .await
+}
+
+#[tokio::test]
+pub async fn test_http_find_node_fork_digest() {
+    // The relay has no eth2 field and knows a node of the network and a foreign one
+    let relay = common::loopback_discv5(None, |_| {}).await;
+    let member = fork_discv5("6a95a1a9").await;
+    let foreign = fork_discv5("bba4da96").await;
+    relay.add_enr(member.local_enr()).unwrap();
+    relay.add_enr(foreign.local_enr()).unwrap();
+
+    let searcher = common::loopback_discv5(None, |_| {}).await;
+    searcher.add_enr(relay.local_enr()).unwrap();
+    let filter = Arc::new(ForkDigestFilter::new([0x6a, 0x95, 0xa1, 0xa9]));
+    let searcher = Arc::new(searcher);
+    let metrics = Arc::new(Metrics::new(Arc::clone(&searcher)));
+    let router = http::router(
+        searcher,
+        Some(Arc::clone(&filter)),
+        Some(Arc::clone(&metrics)),
+    );
+
+    let target = hex::encode(member.local_enr().node_id().raw());
+    let (status, enrs) = call::<Vec<EnrDetails>>(
+        &router,
+        "POST",
+        "/find-node",
+        Some(serde_json::json!({ "target": target })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let enrs = enrs.unwrap();
+    assert!(!enrs.is_empty());
+    assert!(enrs.iter().all(|enr| enr.node_id == target));
+    assert!(filter.rejected().contains_key("none"));
+    // The lookup is recorded in the metrics
+    let encoded = metrics.encode().unwrap();
+    assert!(encoded.contains("discv5_queries_total{source=\"http\",result=\"success\"} 1"));
+}
@@ -0,0 +1,88 @@
+use std::process::Command;
+
+use discv5::enr::CombinedKey;
+use discv5_cli::keys::{format, mine, KeyFormat, KeyInput, KeyType};
+
+#[test]
+pub fn test_key_format_round_trip() -> std::result::Result<(), eyre::Report> {
+    for (key, key_type) in [
+        (CombinedKey::generate_secp256k1(), KeyType::Secp256k1),
+        (CombinedKey::generate_ed25519(), KeyType::Ed25519),
+    ] {
+        for key_format in [KeyFormat::Hex, KeyFormat::Protobuf, KeyFormat::Pem] {
+            let encoded = format::encode_str(&key, key_format)?;
+            let decoded = format::decode_str(&encoded, key_format, key_type)?;
+            assert_eq!(decoded.encode(), key.encode(), "{key_format:?}");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+pub fn test_keystore_round_trip() -> std::result::Result<(), eyre::Report> {
+    let dir = std::env::temp_dir().join(format!("discv5-cli-keystore-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("keystore.json").to_string_lossy().to_string();
+
+    let key = CombinedKey::generate_secp256k1();
+    format::write(&key, KeyFormat::Json, Some(&path), Some("password"))?;
+    let mut input = KeyInput {
+        key: None,
+        key_file: Some(path),
+        format: KeyFormat::Json,
+        key_type: KeyType::Secp256k1,
+        password: Some("password".to_string()),
+    };
+    assert_eq!(format::decode(&input)?.encode(), key.encode());
+
+    // A wrong password does not decrypt the keystore
+    input.password = Some("wrong".to_string());
+    assert!(format::decode(&input).is_err());
+    // Only secp256k1 keys can be stored in a keystore
+    let ed25519 = CombinedKey::generate_ed25519();
+    assert!(format::write(
+        &ed25519,
+        KeyFormat::Json,
+        input.key_file.as_deref(),
+        Some("p")
+    )
+    .is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+pub fn test_key_error_exit_code() -> std::result::Result<(), eyre::Report> {
+    let status = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args(["keys", "inspect", "--key", "not-a-key"])
+        .output()?
+        .status;
+    assert_eq!(status.code(), Some(1));
+
+    let key = format::encode_str(&CombinedKey::generate_secp256k1(), KeyFormat::Hex)?;
+    let status = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args(["keys", "inspect", "--key", &key])
+        .output()?
+        .status;
+    assert!(status.success());
+    Ok(())
+}
+
+#[test]
+pub fn test_mine_criteria() {
+    let target = discv5::enr::NodeId::new(&[0u8; 32]);
+    let criteria = mine::Criteria::Distance {
+        target,
+        distance: 255,
+    };
+    let (key, _) = mine::mine(&criteria, KeyType::Secp256k1, 1);
+    let node_id = discv5::enr::NodeId::from(discv5::enr::EnrKey::public(&key));
+    // Distance 255 from the zero id means the first bit is unset and the second is set.
+    assert_eq!(node_id.raw()[0] >> 6, 0b01);
+
+    let criteria = mine::Criteria::Prefix("a".to_string());
+    let (key, _) = mine::mine(&criteria, KeyType::Ed25519, 2);
+    let node_id = discv5::enr::NodeId::from(discv5::enr::EnrKey::public(&key));
+    assert_eq!(node_id.raw()[0] >> 4, 0xa);
+}
@@ -0,0 +1,62 @@
+use std::{net::Ipv4Addr, sync::Arc, time::Duration};
+
+use discv5::{enr, Event, Key};
+use discv5_cli::server::{
+    event_bus::BusEvent,
+    services::metrics::{Metrics, QuerySource},
+};
+
+mod common;
+use common::local_discv5;
+
+#[tokio::test]
+pub async fn test_metrics_encoding() {
+    let discv5 = local_discv5().await;
+
+    let node = enr::Enr::builder()
+        .ip4(Ipv4Addr::new(10, 0, 0, 1))
+        .udp4(9000)
+        .build(&enr::CombinedKey::generate_secp256k1())
+        .unwrap();
+    let bucket = Key::from(discv5.local_enr().node_id())
+        .log2_distance(&Key::from(node.node_id()))
+        .unwrap();
+    discv5.add_enr(node.clone()).unwrap();
+
+    let metrics = Metrics::new(Arc::new(discv5));
+    metrics.record_event(&BusEvent::Discv5(Event::SessionEstablished(
+        node.clone(),
+        "10.0.0.1:9000".parse().unwrap(),
+    )));
+    metrics.record_event(&BusEvent::Discv5(Event::Discovered(node.clone())));
+    // TALK requests handed to the TALK responder are counted too
+    metrics.record_event(&BusEvent::TalkRequest {
+        node_id: node.node_id(),
+        protocol: b"eth".to_vec(),
+    });
+    metrics.record_query(
+        QuerySource::Query,
+        Duration::from_millis(300),
+        &Ok::<_, ()>(vec![node]),
+    );
+
+    let encoded = metrics.encode().unwrap();
+    for line in [
+        "discv5_sessions_established_total{family=\"ipv4\"} 1".to_string(),
+        "discv5_events_total{type=\"discovered\"} 1".to_string(),
+        "discv5_events_total{type=\"talk_request\"} 1".to_string(),
+        "discv5_queries_total{source=\"query\",result=\"success\"} 1".to_string(),
+        "discv5_query_duration_seconds_count{source=\"query\"} 1".to_string(),
+        "discv5_query_found_nodes_bucket{le=\"1.0\",source=\"query\"} 1".to_string(),
+        format!("discv5_bucket_disconnected_nodes{{bucket=\"{bucket}\"}} 1"),
+        "discv5_connected_peers 0".to_string(),
+        "discv5_active_sessions 0".to_string(),
+    ] {
+        assert!(
+            encoded.contains(&line),
+            "{} missing from\n{}",
+            line,
+            encoded
+        );
+    }
+}
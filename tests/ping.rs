@@ -0,0 +1,88 @@
+use std::{net::SocketAddr, process::Command, time::Duration};
+
+use discv5_cli::{
+    ping::{self, RttStatistics},
+    request_enr::target::Target,
+    utils::enr_ext::EnrExt,
+};
+
+mod common;
+use common::{free_port_v4, local_discv5, loopback_enr};
+
+#[test]
+pub fn test_rtt_statistics() {
+    assert_eq!(RttStatistics::from_rtts(&[]), None);
+
+    let stats = RttStatistics::from_rtts(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).unwrap();
+    assert_eq!(stats.min, 2.0);
+    assert_eq!(stats.max, 9.0);
+    assert_eq!(stats.avg, 5.0);
+    assert_eq!(stats.stddev, 2.0);
+}
+
+#[tokio::test]
+pub async fn test_loopback_ping() {
+    let remote = local_discv5().await;
+    let local = local_discv5().await;
+    let local_address = SocketAddr::new(
+        local.local_enr().ip4().unwrap().into(),
+        local.local_enr().udp4().unwrap(),
+    );
+
+    // The first ping to a new node performs the handshake
+    let report = ping::ping(
+        &local,
+        &remote.local_enr(),
+        3,
+        Duration::from_millis(10),
+        true,
+    )
+    .await;
+    assert_eq!(report.sent, 3);
+    assert_eq!(report.received(), 3);
+    assert!(report.handshake_rtt.is_some());
+    assert_eq!(report.rtts.len(), 2);
+    assert_eq!(
+        report.observed.into_iter().collect::<Vec<_>>(),
+        vec![local_address]
+    );
+
+    // Requesting the ENR of a multiaddr target establishes the session first
+    let local = local_discv5().await;
+    let target = remote.local_enr().multiaddr()[0]
+        .to_string()
+        .parse::<Target>()
+        .unwrap();
+    let enr = target.resolve(&local).await.unwrap();
+    let report = ping::ping(&local, &enr, 2, Duration::from_millis(10), false).await;
+    assert_eq!(report.handshake_rtt, None);
+    assert_eq!(report.rtts.len(), 2);
+}
+
+#[test]
+pub fn test_ping_error_exit_code() -> std::result::Result<(), eyre::Report> {
+    let status = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args(["ping", "--target", "not-a-target"])
+        .output()?
+        .status;
+    assert_eq!(status.code(), Some(1));
+
+    // A node that never answers loses every ping
+    let (unresponsive, _) = loopback_enr(free_port_v4(), None, |_| {});
+    let status = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args([
+            "ping",
+            "--target",
+            &unresponsive.to_base64(),
+            "--count",
+            "1",
+            "--listen-addresses",
+            "127.0.0.1",
+            "--listen-port",
+            &free_port_v4().to_string(),
+        ])
+        .output()?
+        .status;
+    assert_eq!(status.code(), Some(1));
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use discv5::enr;
+use discv5_cli::portal::wire::{Content, Message, PingPayload};
+
+fn decode_hex(s: &str) -> Vec<u8> {
+    hex::decode(s.trim_start_matches("0x")).unwrap()
+}
+
+#[test]
+pub fn test_wire_vectors() {
+    // Test vectors of the Portal wire protocol specification
+    let find_nodes = Message::FindNodes {
+        distances: vec![256, 255],
+    };
+    assert_eq!(find_nodes.encode(), decode_hex("0x02040000000001ff00"));
+
+    let nodes = Message::Nodes {
+        total: 1,
+        enrs: vec![],
+    };
+    assert_eq!(nodes.encode(), decode_hex("0x030105000000"));
+
+    let find_content = Message::FindContent {
+        content_key: decode_hex("0x706f7274616c"),
+    };
+    assert_eq!(
+        find_content.encode(),
+        decode_hex("0x0404000000706f7274616c")
+    );
+
+    let connection_id = Message::Content(Content::ConnectionId([0x01, 0x02]));
+    assert_eq!(connection_id.encode(), decode_hex("0x05000102"));
+
+    let content = Message::Content(Content::Content(decode_hex(
+        "0x7468652063616b652069732061206c6965",
+    )));
+    assert_eq!(
+        content.encode(),
+        decode_hex("0x05017468652063616b652069732061206c6965")
+    );
+
+    for message in [find_nodes, nodes, find_content, connection_id, content] {
+        assert_eq!(Message::decode(&message.encode()).unwrap(), message);
+    }
+}
+
+#[test]
+pub fn test_wire_round_trip() {
+    let enrs: Vec<_> = (0..3)
+        .map(|_| {
+            enr::Enr::builder()
+                .build(&enr::CombinedKey::generate_secp256k1())
+                .unwrap()
+        })
+        .collect();
+    let payload = PingPayload::Capabilities {
+        client_info: b"trin/0.1".to_vec(),
+        data_radius: [0xff; 32],
+        capabilities: vec![0, 1, 2, 65535],
+    };
+    let messages = [
+        Message::Ping {
+            enr_seq: 1,
+            payload_type: payload.payload_type(),
+            payload: payload.encode(),
+        },
+        Message::Nodes {
+            total: 2,
+            enrs: enrs.clone(),
+        },
+        Message::Content(Content::Enrs(enrs)),
+    ];
+    for message in messages {
+        assert_eq!(Message::decode(&message.encode()).unwrap(), message);
+    }
+    assert_eq!(
+        PingPayload::decode(payload.payload_type(), &payload.encode()).unwrap(),
+        payload
+    );
+
+    assert!(Message::decode(&[]).is_err());
+    assert!(Message::decode(&[0x09]).is_err());
+    assert!(Message::decode(&decode_hex("0x0305000000")).is_err());
+}
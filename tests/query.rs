@@ -0,0 +1,130 @@
+use std::{
+    process::{Child, Command, Stdio},
+    sync::Arc,
+    time::Duration,
+};
+
+use discv5_cli::{
+    server::services::{
+        export::Discoveries,
+        graph,
+        query::{self, PeerLookup},
+    },
+    utils::{local_node::LocalNode, raw_node::RawNode},
+};
+
+mod common;
+use common::{free_port_v4, loopback_discv5, loopback_enr};
+
+/// Kills the server once the test is done.
+struct ServerProcess(Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+/// The short-lived nodes the lookup sends its requests from.
+async fn lookup_nodes(count: usize) -> Vec<RawNode> {
+    let local_node = LocalNode {
+        listen_addresses: "127.0.0.1".to_string(),
+        listen_port: 0,
+        listen_port_v6: None,
+    };
+    let mut nodes = Vec::new();
+    for _ in 0..count {
+        nodes.push(RawNode::start(&local_node, false).await.unwrap());
+    }
+    nodes
+}
+
+#[tokio::test]
+pub async fn test_peer_lookup() {
+    // The searcher knows a node that knows the target, and a node that does not answer
+    let target = loopback_discv5(None, |_| {}).await;
+    let peer = loopback_discv5(None, |_| {}).await;
+    peer.add_enr(target.local_enr()).unwrap();
+    let (unresponsive, _) = loopback_enr(free_port_v4(), None, |_| {});
+    let searcher = Arc::new(loopback_discv5(None, |_| {}).await);
+    searcher.add_enr(peer.local_enr()).unwrap();
+    searcher.add_enr(unresponsive.clone()).unwrap();
+
+    let mut nodes = lookup_nodes(3).await;
+    let lookup: PeerLookup =
+        query::peer_lookup(&searcher, &mut nodes, target.local_enr().node_id(), None).await;
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(lookup.enrs[0], target.local_enr());
+    assert!(lookup.enrs.contains(&peer.local_enr()));
+    assert!(lookup
+        .responses
+        .contains(&(peer.local_enr(), vec![target.local_enr()])));
+    assert_eq!(lookup.unresponsive, vec![unresponsive.clone()]);
+
+    // The peer graph has an edge from the peer to the node it returned
+    let discoveries = Discoveries::new(100);
+    discoveries.record_lookup(&lookup);
+    let nodes = discoveries.nodes();
+    let node = |node_id: &str| nodes.iter().find(|node| node.node_id == node_id).unwrap();
+    let peer_id = hex::encode(peer.local_enr().node_id().raw());
+    let target_id = hex::encode(target.local_enr().node_id().raw());
+    assert_eq!(node(&target_id).found_by, vec![peer_id.clone()]);
+    assert_eq!(node(&peer_id).responsive, Some(true));
+    assert_eq!(
+        node(&hex::encode(unresponsive.node_id().raw())).responsive,
+        Some(false)
+    );
+    assert!(graph::to_dot(&nodes).contains(&format!("\"{peer_id}\" -> \"{target_id}\"")));
+}
+
+#[tokio::test]
+pub async fn test_query_peer_graph_export() {
+    // The server is bootstrapped from a node that knows another node
+    let peer = loopback_discv5(None, |_| {}).await;
+    let target = loopback_discv5(Some(&peer.local_enr()), |_| {}).await;
+    peer.add_enr(target.local_enr()).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("discv5-cli-query-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("peers.dot");
+    let server = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args([
+            "server",
+            "-l",
+            "127.0.0.1",
+            "-p",
+            &free_port_v4().to_string(),
+            "-e",
+            &peer.local_enr().to_base64(),
+            "-b",
+            "1",
+            "--export",
+            path.to_str().unwrap(),
+            "--export-interval",
+            "1",
+            "query",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let _server = ServerProcess(server);
+
+    // Lookups of random node ids ask the peer for the bucket of the target most of the time
+    let edge = format!(
+        "\"{}\" -> \"{}\"",
+        hex::encode(peer.local_enr().node_id().raw()),
+        hex::encode(target.local_enr().node_id().raw())
+    );
+    let mut found = false;
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        if std::fs::read_to_string(&path).is_ok_and(|dot| dot.contains(&edge)) {
+            found = true;
+            break;
+        }
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(found, "{} missing from the exported peer graph", edge);
+}
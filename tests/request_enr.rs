@@ -0,0 +1,293 @@
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    process::Command,
+    time::Duration,
+};
+
+use discv5::{enr, ConfigBuilder, Discv5, ListenConfig, RequestError};
+use discv5_cli::{
+    request_enr::{
+        batch,
+        failure::{Failure, RetryPolicy},
+        output::{EnrDetails, Eth2Details},
+        target::Target,
+    },
+    utils::{enr_ext::EnrExt, eth2::EnrForkId, listen, local_node::LocalNode},
+};
+
+mod common;
+use common::{local_discv5, local_discv5_with};
+
+#[test]
+pub fn test_listen_addresses() {
+    assert_eq!(
+        listen::parse_addresses("0.0.0.0").unwrap(),
+        (Some(Ipv4Addr::UNSPECIFIED), None)
+    );
+    assert_eq!(
+        listen::parse_addresses("::1").unwrap(),
+        (None, Some(Ipv6Addr::LOCALHOST))
+    );
+    assert_eq!(
+        listen::parse_addresses("127.0.0.1, ::").unwrap(),
+        (Some(Ipv4Addr::LOCALHOST), Some(Ipv6Addr::UNSPECIFIED))
+    );
+    assert!(listen::parse_addresses("127.0.0.1,localhost").is_err());
+
+    assert!(matches!(
+        listen::listen_config(None, Some(Ipv6Addr::LOCALHOST), 9000, None),
+        ListenConfig::Ipv6 { port: 9000, .. }
+    ));
+    assert!(matches!(
+        listen::listen_config(
+            Some(Ipv4Addr::LOCALHOST),
+            Some(Ipv6Addr::LOCALHOST),
+            9000,
+            Some(9001)
+        ),
+        ListenConfig::DualStack {
+            ipv4_port: 9000,
+            ipv6_port: 9001,
+            ..
+        }
+    ));
+
+    // An ipv6 target adds the unspecified ipv6 address to an ipv4 only local node
+    let local_node = LocalNode {
+        listen_addresses: "0.0.0.0".to_string(),
+        listen_port: 9000,
+        listen_port_v6: None,
+    };
+    let (enr, _, config) = local_node.build(true, |_| {}).unwrap();
+    assert!(matches!(
+        config.listen_config,
+        ListenConfig::DualStack {
+            ipv6: Ipv6Addr::UNSPECIFIED,
+            ipv6_port: 9000,
+            ..
+        }
+    ));
+    assert_eq!(enr.udp6(), Some(9000));
+    let (enr, _, config) = local_node.build(false, |_| {}).unwrap();
+    assert!(matches!(config.listen_config, ListenConfig::Ipv4 { .. }));
+    assert_eq!(enr.udp6(), None);
+}
+
+#[test]
+pub fn test_target_parsing() {
+    assert!(matches!(
+        "/ip4/127.0.0.1/udp/9000/p2p/16Uiu2HAmSH2XVgZqYHWucap5kuPzLnt2TsNQkoppVxB5eJGvaXwm"
+            .parse::<Target>(),
+        Ok(Target::Multiaddr(_))
+    ));
+    let ipv6 = "/ip6/::1/udp/9000/p2p/16Uiu2HAmSH2XVgZqYHWucap5kuPzLnt2TsNQkoppVxB5eJGvaXwm"
+        .parse::<Target>()
+        .unwrap();
+    assert!(ipv6.is_ipv6());
+    assert!("garbage".parse::<Target>().is_err());
+}
+
+#[test]
+pub fn test_enode_target() {
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let enr = enr::Enr::builder().build(&enr_key).unwrap();
+    let enr::CombinedPublicKey::Secp256k1(public_key) = enr.public_key() else {
+        panic!("secp256k1 key expected");
+    };
+    let uncompressed = hex::encode(&public_key.to_encoded_point(false).as_bytes()[1..]);
+
+    let target = format!("enode://{uncompressed}@10.0.0.1:30303?discport=30301")
+        .parse::<Target>()
+        .unwrap();
+    assert_eq!(target.node_id(), Some(enr.node_id()));
+    assert_eq!(target.socket(), Some("10.0.0.1:30301".parse().unwrap()));
+
+    let compressed = hex::encode(public_key.to_encoded_point(true).as_bytes());
+    let target = format!("{compressed}@[::1]:9000")
+        .parse::<Target>()
+        .unwrap();
+    assert_eq!(target.node_id(), Some(enr.node_id()));
+    assert!(target.is_ipv6());
+
+    // A node id alone cannot be contacted
+    let node_id = hex::encode(enr.node_id().raw());
+    assert!(format!("{node_id}@10.0.0.1:9000")
+        .parse::<Target>()
+        .is_err());
+}
+
+#[tokio::test]
+pub async fn test_batch_request() {
+    let remote = local_discv5().await;
+    let local = local_discv5().await;
+
+    let remote_enr = remote.local_enr();
+    let multiaddr = remote_enr.multiaddr()[0].to_string();
+    let targets = vec![
+        (multiaddr.clone(), multiaddr.parse::<Target>()),
+        (
+            remote_enr.to_base64(),
+            remote_enr.to_base64().parse::<Target>(),
+        ),
+        ("garbage".to_string(), "garbage".parse::<Target>()),
+    ];
+    let inputs: Vec<String> = targets.iter().map(|(input, _)| input.clone()).collect();
+
+    // The invalid target fails first, but is reported in input order
+    let reports = batch::request_all(&local, targets, 3, RetryPolicy::default()).await;
+    assert_eq!(
+        reports
+            .iter()
+            .map(|report| report.target.clone())
+            .collect::<Vec<_>>(),
+        inputs
+    );
+    for report in reports {
+        if report.target == "garbage" {
+            assert!(!report.success);
+            assert_eq!(report.failure, Some("invalid-target"));
+            assert!(report.error.is_some());
+        } else {
+            assert!(report.success, "{:?}", report.error);
+            assert_eq!(report.attempts, 1);
+            assert_eq!(report.seq, Some(remote_enr.seq()));
+            assert_eq!(
+                report.node_id,
+                Some(hex::encode(remote_enr.node_id().raw()))
+            );
+        }
+    }
+}
+
+#[test]
+pub fn test_failure_classification() {
+    assert_eq!(Failure::from(RequestError::Timeout), Failure::NoResponse);
+    assert_eq!(
+        Failure::from(RequestError::InvalidRemotePacket).kind(),
+        "handshake-failed"
+    );
+    assert_eq!(
+        Failure::from(RequestError::InvalidRemoteEnr).kind(),
+        "invalid-enr"
+    );
+    assert!(Failure::NoResponse.is_retryable());
+    assert!(!Failure::InvalidEnr(String::new()).is_retryable());
+
+    let policy = RetryPolicy {
+        retries: 3,
+        backoff: Duration::from_millis(100),
+    };
+    assert_eq!(policy.delay(1), Duration::from_millis(100));
+    assert_eq!(policy.delay(3), Duration::from_millis(400));
+}
+
+#[tokio::test]
+pub async fn test_retries_without_response() {
+    let local = local_discv5_with(|config| {
+        config
+            .request_timeout(Duration::from_millis(200))
+            .request_retries(0);
+    })
+    .await;
+
+    // Nothing listens on the target port
+    let target = format!(
+        "/ip4/127.0.0.1/udp/{}/p2p/16Uiu2HAmSH2XVgZqYHWucap5kuPzLnt2TsNQkoppVxB5eJGvaXwm",
+        common::free_port_v4()
+    )
+    .parse::<Target>()
+    .unwrap();
+    let policy = RetryPolicy {
+        retries: 2,
+        backoff: Duration::from_millis(10),
+    };
+    let (result, attempts) = target.request_with_retries(&local, policy).await;
+    assert_eq!(result.unwrap_err(), Failure::NoResponse);
+    assert_eq!(attempts, 3);
+}
+
+#[test]
+pub fn test_json_output() {
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let fork_id = hex::decode("6a95a1a9040000000000000000000000").unwrap();
+    let enr = enr::Enr::builder()
+        .ip4(Ipv4Addr::LOCALHOST)
+        .udp4(9000)
+        .tcp4(9001)
+        .add_value("eth2", &fork_id.as_slice())
+        .build(&enr_key)
+        .unwrap();
+
+    let fork_id = EnrForkId::from_enr(&enr).unwrap().unwrap();
+    assert_eq!(fork_id.fork_digest, [0x6a, 0x95, 0xa1, 0xa9]);
+    assert_eq!(fork_id.next_fork_epoch, 0);
+    assert!(EnrForkId::decode(&[0; 4]).is_err());
+
+    let details = EnrDetails::from(&enr);
+    assert_eq!(details.ip4, Some(Ipv4Addr::LOCALHOST));
+    assert_eq!(details.udp4, Some(9000));
+    assert_eq!(details.ip6, None);
+    assert_eq!(details.multiaddrs.len(), 2);
+    assert_eq!(
+        details.eth2,
+        Some(Eth2Details::ForkId {
+            fork_digest: "0x6a95a1a9".into(),
+            next_fork_version: "0x04000000".into(),
+            next_fork_epoch: 0,
+        })
+    );
+    for key in ["id", "ip", "secp256k1", "tcp", "udp", "eth2"] {
+        assert!(details.keys.contains_key(key), "{}", key);
+    }
+
+    let json: serde_json::Value = serde_json::to_value(&details).unwrap();
+    assert_eq!(json["node_id"], hex::encode(enr.node_id().raw()));
+    assert_eq!(json["eth2"]["fork_digest"], "0x6a95a1a9");
+}
+
+#[tokio::test]
+pub async fn test_ipv6_request() {
+    let port = common::free_port(Ipv6Addr::LOCALHOST);
+    let enr_key = enr::CombinedKey::generate_secp256k1();
+    let enr = enr::Enr::builder()
+        .ip6(Ipv6Addr::LOCALHOST)
+        .udp6(port)
+        .build(&enr_key)
+        .unwrap();
+    let config =
+        ConfigBuilder::new(ListenConfig::from_ip(Ipv6Addr::LOCALHOST.into(), port)).build();
+    let mut remote: Discv5 = Discv5::new(enr, enr_key, config).unwrap();
+    remote.start().await.unwrap();
+
+    // The local node listens on ipv4 only and adds an ipv6 socket for the target
+    let local_node = LocalNode {
+        listen_addresses: "127.0.0.1".to_string(),
+        listen_port: common::free_port_v4(),
+        listen_port_v6: None,
+    };
+    let local = local_node.start(true, |_| {}).await.unwrap();
+
+    let remote_enr = remote.local_enr();
+    let target = remote_enr.multiaddr()[0]
+        .to_string()
+        .parse::<Target>()
+        .unwrap();
+    assert!(target.is_ipv6());
+    let (result, attempts) = target
+        .request_with_retries(&local, RetryPolicy::default())
+        .await;
+    let enr = result.unwrap();
+    assert_eq!(attempts, 1);
+    assert_eq!(enr.node_id(), remote_enr.node_id());
+    assert_eq!(enr.udp6(), Some(port));
+}
+
+#[test]
+pub fn test_missing_targets_file() -> std::result::Result<(), eyre::Report> {
+    let output = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args(["request-enr", "--targets-file", "does-not-exist.txt"])
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("Failed to read the targets file"));
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use discv5::{enr, Discv5};
+use discv5_cli::{
+    server::{
+        command::SubnetSearch,
+        services::{metrics::Metrics, subnets},
+    },
+    utils::eth2,
+};
+
+mod common;
+use common::loopback_discv5;
+
+type Enr = enr::Enr<enr::CombinedKey>;
+
+/// Starts a local discv5 server advertising the subnet bitfields, in the furthest bucket of `peer`
+/// if given.
+async fn subnet_discv5(
+    attnets: Option<[u8; 8]>,
+    syncnets: Option<u8>,
+    peer: Option<&Enr>,
+) -> Discv5 {
+    loopback_discv5(peer, |builder| {
+        if let Some(attnets) = &attnets {
+            builder.add_value("attnets", &attnets.as_slice());
+        }
+        if let Some(syncnets) = &syncnets {
+            builder.add_value("syncnets", &[*syncnets].as_slice());
+        }
+    })
+    .await
+}
+
+#[test]
+pub fn test_subnet_bitfields() {
+    assert_eq!(
+        eth2::decode_bitvector(&[0b0010_0001, 0, 0, 0, 0, 0, 0, 0x80], 64),
+        Ok(vec![0, 5, 63])
+    );
+    assert_eq!(eth2::decode_bitvector(&[0; 8], 64), Ok(vec![]));
+    assert_eq!(eth2::decode_bitvector(&[0b1010], 4), Ok(vec![1, 3]));
+    assert!(eth2::decode_bitvector(&[0; 4], 64).is_err());
+    assert!(eth2::decode_bitvector(&[0b1_0010], 4).is_err());
+}
+
+#[tokio::test]
+pub async fn test_subnet_search() {
+    // The searcher only knows a node without subnets, which knows the subnet nodes
+    let relay = subnet_discv5(None, None, None).await;
+    let attnet = subnet_discv5(
+        Some([0b0010_0000, 0, 0, 0, 0, 0, 0, 0]),
+        Some(0),
+        Some(&relay.local_enr()),
+    )
+    .await;
+    let syncnet = subnet_discv5(Some([0; 8]), Some(0b10), Some(&relay.local_enr())).await;
+    relay.add_enr(attnet.local_enr()).unwrap();
+    relay.add_enr(syncnet.local_enr()).unwrap();
+
+    assert_eq!(eth2::attnets(&attnet.local_enr()), Some(Ok(vec![5])));
+    assert_eq!(eth2::syncnets(&syncnet.local_enr()), Some(Ok(vec![1])));
+    assert_eq!(eth2::attnets(&relay.local_enr()), None);
+
+    let search = SubnetSearch {
+        attnets: vec![5],
+        syncnets: vec![2],
+        matches: 1,
+        max_queries: 20,
+        ..Default::default()
+    };
+    assert!(subnets::matches(&search, &attnet.local_enr()));
+    assert!(!subnets::matches(&search, &syncnet.local_enr()));
+    assert!(!subnets::matches(&search, &relay.local_enr()));
+
+    let searcher = subnet_discv5(None, None, None).await;
+    searcher.add_enr(relay.local_enr()).unwrap();
+    let found = subnets::search(Arc::new(searcher), &search, Duration::ZERO, None, None).await;
+    assert_eq!(found.len(), 1);
+    assert_eq!(
+        found[0].node_id,
+        hex::encode(attnet.local_enr().node_id().raw())
+    );
+    assert_eq!(found[0].enr, attnet.local_enr().to_base64());
+    assert_eq!(found[0].attnets, vec![5]);
+    assert!(found[0].syncnets.is_empty());
+}
+
+#[tokio::test]
+pub async fn test_subnet_search_break_time() {
+    // Lookups without any peers return at once, the search waits between them
+    let search = SubnetSearch {
+        attnets: vec![5],
+        matches: 1,
+        max_queries: 3,
+        ..Default::default()
+    };
+    let searcher = Arc::new(subnet_discv5(None, None, None).await);
+    let metrics = Arc::new(Metrics::new(Arc::clone(&searcher)));
+    let start = Instant::now();
+    let found = subnets::search(
+        searcher,
+        &search,
+        Duration::from_millis(200),
+        None,
+        Some(Arc::clone(&metrics)),
+    )
+    .await;
+    assert!(found.is_empty());
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    // Every lookup is recorded in the metrics
+    let encoded = metrics.encode().unwrap();
+    let line = "discv5_query_duration_seconds_count{source=\"subnet_search\"} 3";
+    assert!(encoded.contains(line), "{} missing from\n{}", line, encoded);
+}
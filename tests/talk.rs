@@ -0,0 +1,152 @@
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use discv5_cli::{
+    server::{
+        event_bus::{BusEvent, EventBus},
+        services::talk::{self as talk_service, TalkHandler, TalkRoute},
+    },
+    talk::{self, PayloadEncoding},
+};
+
+mod common;
+use common::{local_discv5, local_discv5_with};
+
+#[test]
+pub fn test_request_parsing() {
+    assert_eq!(talk::parse_protocol("eth").unwrap(), b"eth".to_vec());
+    assert_eq!(talk::parse_protocol("0x500b").unwrap(), vec![0x50, 0x0b]);
+    assert!(talk::parse_protocol("0xzz").is_err());
+
+    assert_eq!(
+        talk::parse_payload("0x0102", PayloadEncoding::Hex).unwrap(),
+        vec![1, 2]
+    );
+    assert_eq!(
+        talk::parse_payload("hi", PayloadEncoding::Utf8).unwrap(),
+        b"hi".to_vec()
+    );
+    assert!(talk::parse_payload("hi", PayloadEncoding::Hex).is_err());
+}
+
+#[test]
+pub fn test_rlp_decoding() {
+    // [0x01, [0x, 0xabcd]]
+    let encoded = hex::decode("c601c48082abcd").unwrap();
+    assert_eq!(
+        talk::decode_rlp(&encoded).as_deref(),
+        Some("[0x01, [0x, 0xabcd]]")
+    );
+    // Trailing bytes are not a single item
+    assert_eq!(talk::decode_rlp(&[0x01, 0x02]), None);
+    assert_eq!(talk::decode_rlp(&[0xc5, 0x01]), None);
+}
+
+#[test]
+pub fn test_talk_route_parsing() {
+    let route = "0x500b=static:c0ffee".parse::<TalkRoute>().unwrap();
+    assert_eq!(route.protocol, Some(vec![0x50, 0x0b]));
+    assert_eq!(route.handler, TalkHandler::Static(vec![0xc0, 0xff, 0xee]));
+
+    let route = "*=echo".parse::<TalkRoute>().unwrap();
+    assert_eq!(route.protocol, None);
+    assert_eq!(route.handler, TalkHandler::Echo);
+
+    let route = "eth=exec:python3 handler.py".parse::<TalkRoute>().unwrap();
+    assert_eq!(route.protocol, Some(b"eth".to_vec()));
+    assert_eq!(
+        route.handler,
+        TalkHandler::Process("python3 handler.py".into())
+    );
+    assert_eq!(
+        "eth=unix:/tmp/talk.sock"
+            .parse::<TalkRoute>()
+            .unwrap()
+            .handler,
+        TalkHandler::Unix("/tmp/talk.sock".into())
+    );
+
+    assert!("echo".parse::<TalkRoute>().is_err());
+    assert!("eth=unknown".parse::<TalkRoute>().is_err());
+    assert!("eth=exec:".parse::<TalkRoute>().is_err());
+}
+
+#[tokio::test]
+pub async fn test_talk_handlers() {
+    let server = local_discv5().await;
+    let routes = [
+        "eth=echo",
+        "0x500b=static:c0ffee",
+        // Replies with the hex encoded request
+        r#"exec=exec:sed -u -e 's/.*"request":"\([^"]*\)".*/\1/'"#,
+        "slow=exec:while read line; do sleep 5; echo 0x01; done",
+    ]
+    .iter()
+    .map(|route| route.parse::<TalkRoute>().unwrap())
+    .collect();
+    let talk_requests = talk_service::start(routes, Duration::from_millis(300)).unwrap();
+    let event_bus = EventBus::start(&server, Some(talk_requests)).await.unwrap();
+    let mut events = event_bus.subscribe();
+
+    let client = local_discv5_with(|config| {
+        config.request_timeout(Duration::from_secs(3));
+    })
+    .await;
+    let enr = server.local_enr();
+    let talk = |protocol: &[u8], request: &[u8]| {
+        client.talk_req(enr.clone(), protocol.to_vec(), request.to_vec())
+    };
+
+    assert_eq!(talk(b"eth", &[1, 2, 3]).await.unwrap(), vec![1, 2, 3]);
+    assert_eq!(
+        talk(&[0x50, 0x0b], &[1]).await.unwrap(),
+        vec![0xc0, 0xff, 0xee]
+    );
+    assert_eq!(
+        talk(b"exec", &[0xab, 0xcd]).await.unwrap(),
+        vec![0xab, 0xcd]
+    );
+    assert_eq!(talk(b"exec", &[0x01]).await.unwrap(), vec![0x01]);
+    assert!(talk(b"unknown", &[1]).await.unwrap().is_empty());
+
+    // A hung handler is answered with an empty response after the timeout, without holding up
+    // other protocols, and is restarted for the next request
+    let start = Instant::now();
+    let (slow, echo) = tokio::join!(talk(b"slow", &[1]), talk(b"eth", &[4]));
+    assert!(slow.unwrap().is_empty());
+    assert_eq!(echo.unwrap(), vec![4]);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(talk(b"slow", &[2]).await.unwrap().is_empty());
+
+    // The requests are still reported to the subscribers of the event bus
+    let mut protocols = Vec::new();
+    while let Ok(Some(event)) =
+        tokio::time::timeout(Duration::from_millis(100), events.recv()).await
+    {
+        if let BusEvent::TalkRequest { protocol, .. } = &*event {
+            protocols.push(protocol.clone());
+        }
+    }
+    assert_eq!(protocols.len(), 8);
+    assert_eq!(protocols[0], b"eth".to_vec());
+}
+
+#[test]
+pub fn test_talk_error_exit_code() -> std::result::Result<(), eyre::Report> {
+    let status = Command::new(env!("CARGO_BIN_EXE_discv5-cli"))
+        .args([
+            "talk",
+            "--target",
+            "not-a-target",
+            "--protocol",
+            "eth",
+            "--payload",
+            "00",
+        ])
+        .output()?
+        .status;
+    assert_eq!(status.code(), Some(1));
+    Ok(())
+}
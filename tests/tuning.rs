@@ -0,0 +1,104 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use discv5::{enr, ConfigBuilder, ListenConfig};
+use discv5_cli::{
+    cli::{Cli, Subcommand},
+    server::{
+        command::{Discv5Tuning, TableFilter},
+        tuning,
+    },
+};
+
+fn enr_with(ip4: Option<Ipv4Addr>, ip6: Option<Ipv6Addr>) -> enr::Enr<enr::CombinedKey> {
+    let mut builder = enr::Enr::builder();
+    if let Some(ip4) = ip4 {
+        builder.ip4(ip4).udp4(9000);
+    }
+    if let Some(ip6) = ip6 {
+        builder.ip6(ip6).udp6(9000);
+    }
+    builder
+        .build(&enr::CombinedKey::generate_secp256k1())
+        .unwrap()
+}
+
+#[test]
+pub fn test_table_filters() {
+    let private = enr_with(Some(Ipv4Addr::new(192, 168, 1, 1)), None);
+    let public = enr_with(Some(Ipv4Addr::new(45, 31, 182, 164)), None);
+    let public6 = enr_with(None, Some("2001:4860::1".parse().unwrap()));
+
+    let any = tuning::table_filter(TableFilter::Any);
+    assert!(any(&private) && any(&public) && any(&public6));
+    let ip6 = tuning::table_filter(TableFilter::Ip6);
+    assert!(!ip6(&public) && ip6(&public6));
+    let ip4 = tuning::table_filter(TableFilter::Ip4);
+    assert!(ip4(&private) && !ip4(&public6));
+    let public_filter = tuning::table_filter(TableFilter::Public);
+    assert!(!public_filter(&private));
+    assert!(public_filter(&public) && public_filter(&public6));
+}
+
+#[test]
+pub fn test_configure() {
+    let mut tuning = Discv5Tuning {
+        query_parallelism: 5,
+        ban_duration: 0,
+        packet_filter: true,
+        filter_total_rate: 20,
+        filter_max_nodes_per_ip: 0,
+        incoming_bucket_limit: 8,
+        ..Default::default()
+    };
+    let mut builder = ConfigBuilder::new(ListenConfig::from_ip(Ipv4Addr::LOCALHOST.into(), 9000));
+    tuning::configure(&tuning, &mut builder).unwrap();
+    let config = builder.build();
+    assert_eq!(config.query_parallelism, 5);
+    assert_eq!(config.ban_duration, None);
+    assert!(config.enable_packet_filter);
+    assert!(config.filter_rate_limiter.is_some());
+    assert_eq!(config.filter_max_nodes_per_ip, None);
+
+    tuning.incoming_bucket_limit = 17;
+    assert!(tuning::configure(&tuning, &mut ConfigBuilder::new(ListenConfig::default())).is_err());
+}
+
+#[test]
+pub fn test_tuning_defaults() {
+    let defaults = Discv5Tuning::default();
+    assert_eq!(defaults.request_timeout, 3);
+    assert_eq!(defaults.query_parallelism, 3);
+    assert_eq!(defaults.session_timeout, 86400);
+    assert_eq!(defaults.incoming_bucket_limit, 8);
+    assert!(!defaults.ip_limit && !defaults.packet_filter);
+
+    // The defaults match the command line defaults
+    let parse = |args: &[&str]| {
+        let args = ["discv5-cli", "server"]
+            .iter()
+            .chain(args)
+            .chain(&["query"]);
+        let cli = Cli::try_parse_with_config(args).unwrap();
+        let Some(Subcommand::Server(server)) = cli.subcommand else {
+            panic!("server command expected");
+        };
+        server.discv5
+    };
+    assert_eq!(parse(&[]), defaults);
+
+    let mut builder = ConfigBuilder::new(ListenConfig::default());
+    tuning::configure(&defaults, &mut builder).unwrap();
+    let config = builder.build();
+    assert_eq!(config.query_parallelism, 3);
+    assert_eq!(config.request_timeout.as_secs(), 3);
+
+    // The boolean options can be set and unset explicitly
+    assert!(parse(&["--ip-limit"]).ip_limit);
+    assert!(parse(&["--ip-limit=true"]).ip_limit);
+    assert!(!parse(&["--ip-limit=false"]).ip_limit);
+    assert!(parse(&["--packet-filter"]).packet_filter);
+    assert!(
+        Cli::try_parse_with_config(["discv5-cli", "server", "--ip-limit", "false", "query"])
+            .is_err()
+    );
+}